@@ -0,0 +1,2 @@
+-- Add synonym links used by the local answer grader
+ALTER TABLE words ADD COLUMN IF NOT EXISTS synonyms JSONB NOT NULL DEFAULT '[]'::jsonb;
@@ -0,0 +1,13 @@
+-- Generated fill-in-the-blank exercises (answers stay server-side until checked)
+CREATE TABLE IF NOT EXISTS cloze_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    word_id VARCHAR NOT NULL REFERENCES words(id) ON DELETE CASCADE,
+    headword VARCHAR NOT NULL,
+    text TEXT NOT NULL,
+    answers JSONB NOT NULL DEFAULT '[]'::jsonb,
+    source VARCHAR(20) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_cloze_items_user_id ON cloze_items(user_id);
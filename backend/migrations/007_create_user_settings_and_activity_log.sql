@@ -0,0 +1,26 @@
+-- Per-user study settings (daily goals and streak rules)
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    daily_new_words_goal INT NOT NULL DEFAULT 5,
+    daily_reviews_goal INT NOT NULL DEFAULT 20,
+    time_zone VARCHAR(64) NOT NULL DEFAULT 'Asia/Tokyo',
+    day_rollover_hour INT NOT NULL DEFAULT 4,
+    streak_freezes_per_month INT NOT NULL DEFAULT 2,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (day_rollover_hour BETWEEN 0 AND 23)
+);
+
+-- Study activity log used for goals and streaks
+-- word_id has no foreign key so that history survives word deletion
+CREATE TABLE IF NOT EXISTS activity_log (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(32) NOT NULL,
+    word_id VARCHAR,
+    correct BOOLEAN,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_activity_log_user_occurred_at ON activity_log(user_id, occurred_at);
@@ -0,0 +1,12 @@
+-- Review scheduling state stored on each word
+ALTER TABLE words ADD COLUMN IF NOT EXISTS due_at TIMESTAMPTZ;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS interval_days INT NOT NULL DEFAULT 0;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS repetitions INT NOT NULL DEFAULT 0;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS lapses INT NOT NULL DEFAULT 0;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS leitner_box INT NOT NULL DEFAULT 1;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS last_reviewed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_words_user_due_at ON words(user_id, due_at);
+
+-- leitner_intervals holds the review interval in days for each box; its length is the box count
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS leitner_intervals JSONB NOT NULL DEFAULT '[1, 2, 4, 8, 16]'::jsonb;
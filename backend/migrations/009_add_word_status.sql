@@ -0,0 +1,7 @@
+-- Learning status: new, learning, reviewing, mastered, suspended, ignored
+ALTER TABLE words ADD COLUMN IF NOT EXISTS status VARCHAR(16) NOT NULL DEFAULT 'new';
+
+-- Words that already have review history are at least in the learning stage
+UPDATE words SET status = 'learning' WHERE status = 'new' AND last_reviewed_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_words_user_status ON words(user_id, status);
@@ -0,0 +1,8 @@
+-- Leech flag and AI-generated memory aids for words that keep failing review
+ALTER TABLE words ADD COLUMN IF NOT EXISTS is_leech BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS mnemonic TEXT;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS etymology_hint TEXT;
+ALTER TABLE words ADD COLUMN IF NOT EXISTS contrasting_example TEXT;
+
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS leech_threshold INT NOT NULL DEFAULT 8;
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS leech_auto_suspend BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,31 @@
+-- Timed exams (TOEIC / Eiken style) and their questions
+CREATE TABLE IF NOT EXISTS exams (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    categories JSONB NOT NULL DEFAULT '[]'::jsonb,
+    question_count INT NOT NULL,
+    time_limit_seconds INT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'in_progress',
+    score INT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deadline_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_exams_user_started_at ON exams(user_id, started_at DESC);
+
+-- word_id has no foreign key so that results survive word deletion
+CREATE TABLE IF NOT EXISTS exam_questions (
+    exam_id UUID NOT NULL REFERENCES exams(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    word_id VARCHAR NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    prompt TEXT NOT NULL,
+    choices JSONB NOT NULL,
+    correct_index INT NOT NULL,
+    selected_index INT,
+    is_correct BOOLEAN,
+    answered_at TIMESTAMPTZ,
+
+    PRIMARY KEY (exam_id, position)
+);
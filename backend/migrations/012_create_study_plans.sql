@@ -0,0 +1,25 @@
+-- Per-user caps for the daily study planner
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS max_new_words_per_day INT NOT NULL DEFAULT 10;
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS max_reviews_per_day INT NOT NULL DEFAULT 100;
+
+-- One plan per user and local day, shared across devices
+CREATE TABLE IF NOT EXISTS study_plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    plan_date DATE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (user_id, plan_date)
+);
+
+CREATE TABLE IF NOT EXISTS study_plan_items (
+    plan_id UUID NOT NULL REFERENCES study_plans(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    word_id VARCHAR NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    completed_at TIMESTAMPTZ,
+
+    PRIMARY KEY (plan_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_study_plan_items_word_id ON study_plan_items(word_id);
@@ -0,0 +1,32 @@
+-- Level estimated by the placement test, shown on the user profile
+ALTER TABLE users ADD COLUMN IF NOT EXISTS cefr_level VARCHAR(2);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS vocab_estimate INT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS level_assessed_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS placement_tests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(16) NOT NULL DEFAULT 'in_progress',
+    cefr_level VARCHAR(2),
+    vocab_estimate INT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_placement_tests_user_id ON placement_tests(user_id);
+
+-- band is the 0-based 1000-word frequency band; pseudowords (is_real = false) catch guessing
+CREATE TABLE IF NOT EXISTS placement_items (
+    test_id UUID NOT NULL REFERENCES placement_tests(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    band INT NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    word VARCHAR(64) NOT NULL,
+    is_real BOOLEAN NOT NULL,
+    choices JSONB NOT NULL DEFAULT '[]'::jsonb,
+    correct_index INT,
+    correct BOOLEAN,
+    answered_at TIMESTAMPTZ,
+
+    PRIMARY KEY (test_id, position)
+);
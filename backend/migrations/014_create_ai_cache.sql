@@ -0,0 +1,10 @@
+-- Cached AI responses. cache_key is a SHA-256 of provider, model, prompt version and normalized inputs
+CREATE TABLE IF NOT EXISTS ai_cache (
+    cache_key CHAR(64) PRIMARY KEY,
+    task VARCHAR(32) NOT NULL,
+    response JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_cache_expires_at ON ai_cache(expires_at);
@@ -0,0 +1,2 @@
+-- Record which prompt template version produced each AI response, so prompt changes can be compared
+ALTER TABLE ai_usage ADD COLUMN IF NOT EXISTS prompt_version VARCHAR(64);
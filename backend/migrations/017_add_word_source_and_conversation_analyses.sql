@@ -0,0 +1,15 @@
+-- Where a word came from; AI suggestions keep the level and reason they were suggested with
+ALTER TABLE words ADD COLUMN IF NOT EXISTS source VARCHAR(16) NOT NULL DEFAULT 'manual';
+ALTER TABLE words ADD COLUMN IF NOT EXISTS source_metadata JSONB;
+
+-- Conversation analyses are kept so their suggestions can be saved as words later
+CREATE TABLE IF NOT EXISTS conversation_analyses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    conversation_text TEXT NOT NULL,
+    result JSONB NOT NULL,
+    prompt_version VARCHAR(64),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_conversation_analyses_user_id ON conversation_analyses(user_id, created_at);
@@ -0,0 +1,2 @@
+-- Extra example sentences accepted from AI generation (the main one stays in `example`)
+ALTER TABLE words ADD COLUMN IF NOT EXISTS examples JSONB NOT NULL DEFAULT '[]'::jsonb;
@@ -0,0 +1,25 @@
+-- Practice conversations with the AI tutor; ending a session stores its conversation analysis
+CREATE TABLE IF NOT EXISTS tutor_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    topic TEXT,
+    cefr_level VARCHAR(2) NOT NULL,
+    focus_words JSONB NOT NULL DEFAULT '[]'::jsonb,
+    status VARCHAR(16) NOT NULL DEFAULT 'active',
+    analysis_id UUID REFERENCES conversation_analyses(id) ON DELETE SET NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ended_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_tutor_sessions_user_started_at ON tutor_sessions(user_id, started_at DESC);
+
+CREATE TABLE IF NOT EXISTS tutor_messages (
+    session_id UUID NOT NULL REFERENCES tutor_sessions(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    role VARCHAR(16) NOT NULL,
+    content TEXT NOT NULL,
+    used_words JSONB NOT NULL DEFAULT '[]'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (session_id, position)
+);
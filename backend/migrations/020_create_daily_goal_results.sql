@@ -0,0 +1,12 @@
+-- Finished days with the goals that were in effect, so that changing goals or the time zone later
+-- does not rewrite past streaks
+CREATE TABLE IF NOT EXISTS daily_goal_results (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    new_words INT NOT NULL,
+    reviews INT NOT NULL,
+    new_words_goal INT NOT NULL,
+    reviews_goal INT NOT NULL,
+
+    PRIMARY KEY (user_id, day)
+);
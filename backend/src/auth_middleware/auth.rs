@@ -23,6 +23,7 @@ pub struct AuthUser {
 }
 
 // Auth.js JWT検証ミドルウェア
+#[allow(clippy::manual_strip)]
 pub async fn auth_middleware(
     State(app_state): State<AppState>,
     mut request: Request,
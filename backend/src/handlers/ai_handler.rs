@@ -1,30 +1,75 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use shuttle_axum::axum::{
-    extract::{Extension, Json, State},
+    extract::{Extension, Json, Path, State},
     http::StatusCode,
     response::IntoResponse,
 };
+use uuid::Uuid;
 
 use crate::auth_middleware::AuthUser;
+use crate::handlers::word_handler::CreateWordRequest;
+use crate::llm::prompt::{self, Prompt, PromptTemplate, PromptVar};
+use crate::llm::structured::require_text;
+use crate::llm::{generate_structured, AiError, AiTask, StructuredOutput, TokenUsage};
+use crate::models::conversation_analysis::ConversationAnalysis;
+use crate::models::user::{CefrLevel, User};
+use crate::models::word::Word;
 use crate::models::AppState;
+use crate::services::ai_cache::{self, CacheStatus};
+use crate::services::ai_stream::{self, StreamRequest};
+use crate::services::ai_usage::{self, AiEndpoint};
+use crate::services::cloze;
+use crate::services::known_words::KnownWords;
+
+// レベルが分からないときの基準
+const DEFAULT_LEVEL: CefrLevel = CefrLevel::B2;
 
 // Request DTOs for AI endpoints
 #[derive(Debug, Deserialize)]
 pub struct ConversationAnalysisRequest {
     pub conversation_text: String,
-    pub user_level: Option<String>, // B2, etc.
+    /// CEFR レベル (B2 など)。省略時はレベル判定テストの結果を使う
+    pub user_level: Option<String>,
+    /// true ならキャッシュを使わずに生成し直す
+    #[serde(default)]
+    pub bypass_cache: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct VocabularyHelpRequest {
     pub context: String,
     pub question: String,
+    pub user_level: Option<String>,
+    #[serde(default)]
+    pub bypass_cache: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WordSuggestionRequest {
     pub user_input: String,
     pub conversation_context: Option<String>,
+    pub user_level: Option<String>,
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WordAutofillRequest {
+    pub word: String,
+    /// 単語を見かけた文 (あれば、その文に合う意味を選ぶ)
+    pub context_sentence: Option<String>,
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExampleSentencesRequest {
+    /// 例文の話題 (work, travel など。空なら日常の場面)
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub count: Option<usize>,
+    pub user_level: Option<String>,
 }
 
 // Response DTOs
@@ -36,283 +81,819 @@ pub struct WordSuggestion {
     pub example: String,
     pub difficulty_level: String,
     pub relevance_reason: String,
+    /// 同じ語 (変化形を含む) をすでに単語帳に登録しているか
+    #[serde(default)]
+    pub already_saved: bool,
+    /// 登録済みの単語の ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub existing_word_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VocabularyHelpResponse {
     pub explanation: String,
+    #[serde(default)]
     pub examples: Vec<String>,
+    #[serde(default)]
     pub usage_tips: String,
+    #[serde(default)]
     pub suggested_word: Option<WordSuggestion>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConversationAnalysisResponse {
     pub suggestions: Vec<WordSuggestion>,
+    #[serde(default)]
     pub conversation_summary: String,
+    #[serde(default)]
     pub learning_points: Vec<String>,
+    /// 保存した分析の ID (提案を単語として登録するときに使う)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis_id: Option<Uuid>,
 }
 
-// Internal Gemini API structs
-#[derive(Serialize)]
-struct GeminiRequest {
-    contents: Vec<GeminiContent>,
+#[derive(Debug, Serialize, Deserialize)]
+struct WordSuggestionsResponse {
+    suggestions: Vec<WordSuggestion>,
 }
 
-#[derive(Serialize)]
-struct GeminiContent {
-    parts: Vec<GeminiPart>,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeechAids {
+    pub mnemonic: String,
+    pub etymology_hint: String,
+    pub contrasting_example: String,
 }
 
-#[derive(Serialize)]
-struct GeminiPart {
-    text: String,
+fn string_array_schema() -> Value {
+    json!({ "type": "array", "items": { "type": "string" } })
 }
 
-#[derive(Deserialize)]
-struct GeminiResponse {
-    candidates: Vec<GeminiCandidate>,
+fn suggestion_list_schema() -> Value {
+    json!({ "type": "array", "items": WordSuggestion::schema() })
 }
 
-#[derive(Deserialize)]
-struct GeminiCandidate {
-    content: GeminiResponseContent,
+/// 単語に合わせて生成した例文
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExampleSentence {
+    pub sentence: String,
+    pub translation: String,
+    pub topic: String,
+    /// 例文に含まれる、学習中の他の単語
+    #[serde(default)]
+    pub reinforced_words: Vec<String>,
 }
 
-#[derive(Deserialize)]
-struct GeminiResponseContent {
-    parts: Vec<GeminiResponsePart>,
+#[derive(Debug, Serialize, Deserialize)]
+struct ExampleSentencesResponse {
+    sentences: Vec<ExampleSentence>,
 }
 
-#[derive(Deserialize)]
-struct GeminiResponsePart {
-    text: String,
+/// AI が補完した単語カードの内容 (CreateWordRequest の形にして返す)
+#[derive(Debug, Serialize, Deserialize)]
+struct WordAutofill {
+    word: String,
+    meaning: String,
+    translation: String,
+    part_of_speech: Vec<String>,
+    phonetic: String,
+    example: String,
+    #[serde(default)]
+    synonyms: Vec<String>,
 }
 
-// Helper function to call Gemini API
-async fn call_gemini_api(api_key: &str, prompt: &str) -> Result<String, (StatusCode, String)> {
-    let client = reqwest::Client::new();
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash-lite:generateContent?key={}",
-        api_key
-    );
+impl StructuredOutput for WordAutofill {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "word": { "type": "string" },
+                "meaning": { "type": "string" },
+                "translation": { "type": "string" },
+                "part_of_speech": string_array_schema(),
+                "phonetic": { "type": "string" },
+                "example": { "type": "string" },
+                "synonyms": string_array_schema()
+            },
+            "required": ["word", "meaning", "translation", "part_of_speech", "phonetic", "example"]
+        })
+    }
 
-    let request = GeminiRequest {
-        contents: vec![GeminiContent {
-            parts: vec![GeminiPart {
-                text: prompt.to_string(),
-            }],
-        }],
-    };
+    fn validate(&self) -> Result<(), String> {
+        require_text("word", &self.word)?;
+        require_text("meaning", &self.meaning)?;
+        require_text("translation", &self.translation)?;
+        require_text("phonetic", &self.phonetic)?;
+        if self
+            .part_of_speech
+            .iter()
+            .all(|part| part.trim().is_empty())
+        {
+            return Err("part_of_speech must not be empty".to_string());
+        }
+        // 例文に見出し語 (変化形を含む) が使われているか
+        match cloze::make_cloze(&self.example, &self.word) {
+            Some(_) => Ok(()),
+            None => Err(format!("example must use \"{}\"", self.word)),
+        }
+    }
+}
 
-    let response = client.post(&url).json(&request).send().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Request failed: {}", e),
-        )
-    })?;
+impl StructuredOutput for ExampleSentencesResponse {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sentences": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sentence": { "type": "string" },
+                            "translation": { "type": "string" },
+                            "topic": { "type": "string" }
+                        },
+                        "required": ["sentence", "translation", "topic"]
+                    }
+                }
+            },
+            "required": ["sentences"]
+        })
+    }
 
-    if !response.status().is_success() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Gemini API error: {}", response.status()),
-        ));
+    fn validate(&self) -> Result<(), String> {
+        if self.sentences.is_empty() {
+            return Err("`sentences` must not be empty".to_string());
+        }
+        for (i, example) in self.sentences.iter().enumerate() {
+            require_text("sentence", &example.sentence)
+                .and_then(|_| require_text("translation", &example.translation))
+                .map_err(|e| format!("sentences[{}]: {}", i, e))?;
+        }
+        Ok(())
     }
+}
 
-    let gemini_response: GeminiResponse = response.json().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("JSON parse error: {}", e),
-        )
-    })?;
-
-    let raw_text = gemini_response
-        .candidates
-        .first()
-        .and_then(|c| c.content.parts.first())
-        .map(|p| p.text.clone())
-        .ok_or((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "No response from Gemini".to_string(),
-        ))?;
-
-    // Strip markdown code blocks if present
-    let cleaned_text = if raw_text.starts_with("```json") && raw_text.ends_with("```") {
-        // Remove ```json at the start and ``` at the end
-        raw_text
-            .strip_prefix("```json")
-            .and_then(|s| s.strip_suffix("```"))
-            .unwrap_or(&raw_text)
-            .trim()
-            .to_string()
-    } else if raw_text.starts_with("```") && raw_text.ends_with("```") {
-        // Remove ``` at the start and end (without json specifier)
-        raw_text
-            .strip_prefix("```")
-            .and_then(|s| s.strip_suffix("```"))
-            .unwrap_or(&raw_text)
-            .trim()
-            .to_string()
-    } else {
-        raw_text
-    };
+impl StructuredOutput for WordSuggestion {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "word": { "type": "string" },
+                "meaning": { "type": "string" },
+                "part_of_speech": { "type": "string" },
+                "example": { "type": "string" },
+                "difficulty_level": { "type": "string" },
+                "relevance_reason": { "type": "string" }
+            },
+            "required": ["word", "meaning", "part_of_speech", "example", "difficulty_level", "relevance_reason"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require_text("word", &self.word)?;
+        require_text("meaning", &self.meaning)?;
+        require_text("example", &self.example)?;
+        match CefrLevel::parse(&self.difficulty_level) {
+            Some(_) => Ok(()),
+            None => Err(format!(
+                "difficulty_level must be one of A1, A2, B1, B2, C1, C2 (got \"{}\")",
+                self.difficulty_level
+            )),
+        }
+    }
+}
+
+impl StructuredOutput for VocabularyHelpResponse {
+    fn schema() -> Value {
+        let mut suggested_word = WordSuggestion::schema();
+        suggested_word["nullable"] = json!(true);
+        json!({
+            "type": "object",
+            "properties": {
+                "explanation": { "type": "string" },
+                "examples": string_array_schema(),
+                "usage_tips": { "type": "string" },
+                "suggested_word": suggested_word
+            },
+            "required": ["explanation", "examples", "usage_tips"],
+            // ストリーミング時に explanation を先に生成させる
+            "propertyOrdering": ["explanation", "examples", "usage_tips", "suggested_word"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require_text("explanation", &self.explanation)?;
+        match &self.suggested_word {
+            Some(word) => word
+                .validate()
+                .map_err(|e| format!("suggested_word: {}", e)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl StructuredOutput for ConversationAnalysisResponse {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "suggestions": suggestion_list_schema(),
+                "conversation_summary": { "type": "string" },
+                "learning_points": string_array_schema()
+            },
+            "required": ["suggestions", "conversation_summary", "learning_points"],
+            // ストリーミング時に conversation_summary を先に生成させる
+            "propertyOrdering": ["conversation_summary", "suggestions", "learning_points"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_suggestions(&self.suggestions)
+    }
+}
+
+impl StructuredOutput for WordSuggestionsResponse {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": { "suggestions": suggestion_list_schema() },
+            "required": ["suggestions"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_suggestions(&self.suggestions)
+    }
+}
+
+impl StructuredOutput for LeechAids {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "mnemonic": { "type": "string" },
+                "etymology_hint": { "type": "string" },
+                "contrasting_example": { "type": "string" }
+            },
+            "required": ["mnemonic", "etymology_hint", "contrasting_example"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require_text("mnemonic", &self.mnemonic)
+    }
+}
+
+fn validate_suggestions(suggestions: &[WordSuggestion]) -> Result<(), String> {
+    if suggestions.is_empty() {
+        return Err("`suggestions` must not be empty".to_string());
+    }
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        suggestion
+            .validate()
+            .map_err(|e| format!("suggestions[{}]: {}", i, e))?;
+    }
+    Ok(())
+}
+
+// 何度も忘れる単語 (リーチ) の覚え方・語源・対比例文を生成 (使用量は単語の持ち主に数える)
+pub(crate) async fn generate_leech_aids(
+    app_state: &AppState,
+    word: &Word,
+) -> Result<LeechAids, (StatusCode, String)> {
+    let prompt = prompt::LEECH_AIDS.render(&[
+        PromptVar::user_input("word", &word.word),
+        PromptVar::user_input("meaning", &word.meaning),
+        PromptVar::user_input(
+            "translation",
+            word.translation.as_deref().unwrap_or("(none)"),
+        ),
+        PromptVar::user_input("example", word.example.as_deref().unwrap_or("(none)")),
+    ]);
+
+    ai_usage::check_quota(app_state, word.user_id).await?;
+    let (aids, usage) = ai_usage::record_on_error(
+        app_state,
+        word.user_id,
+        AiEndpoint::LeechAids,
+        prompt.template,
+        generate_structured(app_state.llm.as_ref(), AiTask::Generation, &prompt.text).await,
+    )
+    .await?;
+    ai_usage::record(
+        app_state,
+        word.user_id,
+        AiEndpoint::LeechAids,
+        prompt.template,
+        usage,
+        false,
+    )
+    .await;
+    Ok(aids)
+}
+
+/// 提案の難易度の基準にするレベル
+///
+/// リクエストで指定されていればそれを、無ければレベル判定テストの結果を使う。どちらも無ければ B2。
+pub(crate) async fn learner_level(
+    app_state: &AppState,
+    user_id: Uuid,
+    requested: Option<&str>,
+) -> Result<CefrLevel, AiError> {
+    if let Some(requested) = requested.filter(|level| !level.trim().is_empty()) {
+        return CefrLevel::parse(requested).ok_or_else(|| {
+            AiError::BadRequest(format!(
+                "user_level must be one of A1, A2, B1, B2, C1, C2 (got \"{}\")",
+                requested
+            ))
+        });
+    }
+
+    let user = User::find_by_id(&app_state.pool, user_id)
+        .await
+        .map_err(|e| AiError::Internal(e.to_string()))?;
+    Ok(user
+        .and_then(|user| user.cefr_level)
+        .and_then(|level| CefrLevel::parse(&level))
+        .unwrap_or(DEFAULT_LEVEL))
+}
+
+// 学習者のレベルと、少し背伸びしたレベル (提案する単語の難易度の上限)
+fn level_vars(level: CefrLevel) -> [PromptVar<'static>; 2] {
+    [
+        PromptVar::text("level", level.as_str()),
+        PromptVar::text("stretch_level", level.next().as_str()),
+    ]
+}
 
-    Ok(cleaned_text)
+fn conversation_analysis_prompt(conversation_text: &str, level: CefrLevel) -> Prompt {
+    let [level, stretch_level] = level_vars(level);
+    prompt::CONVERSATION_ANALYSIS.render(&[
+        PromptVar::user_input("conversation", conversation_text),
+        level,
+        stretch_level,
+    ])
+}
+
+fn vocabulary_help_prompt(context: &str, question: &str, level: CefrLevel) -> Prompt {
+    let [level, stretch_level] = level_vars(level);
+    prompt::VOCABULARY_HELP.render(&[
+        PromptVar::user_input("context", context),
+        PromptVar::user_input("question", question),
+        level,
+        stretch_level,
+    ])
 }
 
 // POST /api/ai/conversation-analysis - 対話後の語彙提案
 pub async fn analyze_conversation_handler(
     State(app_state): State<AppState>,
-    Extension(_auth_user): Extension<AuthUser>,
+    Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<ConversationAnalysisRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let prompt = format!(
-        r#"
-You are an AI tutor for English learners at B2 level. Analyze this conversation and suggest 3-5 vocabulary words that would help the user improve their English.
-
-Conversation:
-{}
-
-Please provide a JSON response with the following structure:
-{{
-  "suggestions": [
-    {{
-      "word": "vocabulary_word",
-      "meaning": "clear definition",
-      "part_of_speech": "noun/verb/adjective/etc",
-      "example": "example sentence using the word",
-      "difficulty_level": "B2/C1",
-      "relevance_reason": "why this word is relevant to the conversation"
-    }}
-  ],
-  "conversation_summary": "brief summary of the conversation topic",
-  "learning_points": ["key learning point 1", "key learning point 2"]
-}}
-
-Focus on words that:
-1. Are appropriate for B2-C1 level
-2. Would have been useful in this conversation
-3. Fill vocabulary gaps shown by the user
-4. Are practical and commonly used
-"#,
-        req.conversation_text
-    );
+) -> Result<impl IntoResponse, AiError> {
+    let level = learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    let (analysis, prompt, cache_status) = analyze_conversation(
+        &app_state,
+        auth_user.user_id,
+        &req.conversation_text,
+        level,
+        req.bypass_cache,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        ai_headers(&prompt, cache_status),
+        Json(analysis),
+    ))
+}
 
-    let gemini_response = call_gemini_api(&app_state.gemini_api_key, &prompt).await?;
+/// 会話を分析し、登録済みの単語に印を付けて保存する (チューターとの会話の終了時にも使う)
+pub(crate) async fn analyze_conversation(
+    app_state: &AppState,
+    user_id: Uuid,
+    conversation_text: &str,
+    level: CefrLevel,
+    bypass_cache: bool,
+) -> Result<(ConversationAnalysisResponse, Prompt, CacheStatus), AiError> {
+    let prompt = conversation_analysis_prompt(conversation_text, level);
+
+    ai_usage::check_quota(app_state, user_id).await?;
+    let known_words = load_known_words(app_state, user_id).await?;
+    let (mut analysis, cache_status, usage): (ConversationAnalysisResponse, _, _) =
+        ai_usage::record_on_error(
+            app_state,
+            user_id,
+            AiEndpoint::ConversationAnalysis,
+            prompt.template,
+            ai_cache::generate_cached(app_state, AiTask::Analysis, &prompt, bypass_cache).await,
+        )
+        .await?;
+    ai_usage::record(
+        app_state,
+        user_id,
+        AiEndpoint::ConversationAnalysis,
+        prompt.template,
+        usage,
+        cache_status == CacheStatus::Hit,
+    )
+    .await;
+
+    mark_known(&mut analysis.suggestions, &known_words);
+    analysis.analysis_id = save_analysis(
+        app_state,
+        user_id,
+        conversation_text,
+        prompt.template,
+        &analysis,
+    )
+    .await;
+
+    Ok((analysis, prompt, cache_status))
+}
 
-    // Parse JSON response from Gemini
-    let analysis: ConversationAnalysisResponse =
-        serde_json::from_str(&gemini_response).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to parse AI response: {}", e),
-            )
-        })?;
+// POST /api/conversation-analysis/stream - 対話後の語彙提案 (SSE)
+pub async fn analyze_conversation_stream_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<ConversationAnalysisRequest>,
+) -> Result<impl IntoResponse, AiError> {
+    let level = learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+    let request = StreamRequest {
+        task: AiTask::Analysis,
+        endpoint: AiEndpoint::ConversationAnalysis,
+        prompt: conversation_analysis_prompt(&req.conversation_text, level),
+        text_field: "conversation_summary",
+        bypass_cache: req.bypass_cache,
+    };
+    let known_words = load_known_words(&app_state, auth_user.user_id).await?;
+    let (state, user_id, template) = (
+        app_state.clone(),
+        auth_user.user_id,
+        request.prompt.template,
+    );
+    let finish = move |mut analysis: ConversationAnalysisResponse| async move {
+        mark_known(&mut analysis.suggestions, &known_words);
+        analysis.analysis_id =
+            save_analysis(&state, user_id, &req.conversation_text, template, &analysis).await;
+        analysis
+    };
 
-    Ok((StatusCode::OK, Json(analysis)))
+    Ok(ai_stream::stream_structured(app_state, auth_user.user_id, request, finish).await)
 }
 
 // POST /api/ai/vocabulary-help - 対話中の語彙ヘルプ
 pub async fn vocabulary_help_handler(
     State(app_state): State<AppState>,
-    Extension(_auth_user): Extension<AuthUser>,
+    Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<VocabularyHelpRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let prompt = format!(
-        r#"
-You are an English vocabulary tutor. The user is having a conversation and has asked for help with vocabulary.
-
-Context: {}
-User's question: {}
-
-Please provide a helpful response in JSON format:
-{{
-  "explanation": "clear explanation answering the user's question",
-  "examples": ["example 1", "example 2", "example 3"],
-  "usage_tips": "practical tips for using this vocabulary",
-  "suggested_word": {{
-    "word": "suggested_word",
-    "meaning": "clear definition",
-    "part_of_speech": "noun/verb/adjective/etc",
-    "example": "example sentence",
-    "difficulty_level": "B2/C1",
-    "relevance_reason": "why this word is helpful"
-  }}
-}}
-
-If the user asked about a specific word, explain it thoroughly. If they're looking for better ways to express something, suggest appropriate alternatives.
-"#,
-        req.context, req.question
-    );
+) -> Result<impl IntoResponse, AiError> {
+    let level = learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    let prompt = vocabulary_help_prompt(&req.context, &req.question, level);
+
+    ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+    let known_words = load_known_words(&app_state, auth_user.user_id).await?;
+    let (mut help_response, cache_status, usage): (VocabularyHelpResponse, _, _) =
+        ai_usage::record_on_error(
+            &app_state,
+            auth_user.user_id,
+            AiEndpoint::VocabularyHelp,
+            prompt.template,
+            ai_cache::generate_cached(&app_state, AiTask::Help, &prompt, req.bypass_cache).await,
+        )
+        .await?;
+    ai_usage::record(
+        &app_state,
+        auth_user.user_id,
+        AiEndpoint::VocabularyHelp,
+        prompt.template,
+        usage,
+        cache_status == CacheStatus::Hit,
+    )
+    .await;
+
+    mark_known(help_response.suggested_word.as_mut_slice(), &known_words);
+
+    Ok((
+        StatusCode::OK,
+        ai_headers(&prompt, cache_status),
+        Json(help_response),
+    ))
+}
 
-    let gemini_response = call_gemini_api(&app_state.gemini_api_key, &prompt).await?;
+// POST /api/vocabulary-help/stream - 対話中の語彙ヘルプ (SSE)
+pub async fn vocabulary_help_stream_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<VocabularyHelpRequest>,
+) -> Result<impl IntoResponse, AiError> {
+    let level = learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+    let request = StreamRequest {
+        task: AiTask::Help,
+        endpoint: AiEndpoint::VocabularyHelp,
+        prompt: vocabulary_help_prompt(&req.context, &req.question, level),
+        text_field: "explanation",
+        bypass_cache: req.bypass_cache,
+    };
+    let known_words = load_known_words(&app_state, auth_user.user_id).await?;
 
-    let help_response: VocabularyHelpResponse =
-        serde_json::from_str(&gemini_response).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to parse AI response: {}", e),
-            )
-        })?;
+    let finish = move |mut help: VocabularyHelpResponse| async move {
+        mark_known(help.suggested_word.as_mut_slice(), &known_words);
+        help
+    };
 
-    Ok((StatusCode::OK, Json(help_response)))
+    Ok(ai_stream::stream_structured(app_state, auth_user.user_id, request, finish).await)
 }
 
 // POST /api/ai/word-suggestions - 単語提案
 pub async fn word_suggestions_handler(
     State(app_state): State<AppState>,
-    Extension(_auth_user): Extension<AuthUser>,
+    Extension(auth_user): Extension<AuthUser>,
     Json(req): Json<WordSuggestionRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let prompt = format!(
-        r#"
-Based on the user's input and conversation context, suggest vocabulary words that would help them express themselves better.
-
-User input: {}
-Context: {}
-
-Provide 3-5 word suggestions in JSON format:
-{{
-  "suggestions": [
-    {{
-      "word": "vocabulary_word",
-      "meaning": "clear definition",
-      "part_of_speech": "noun/verb/adjective/etc",
-      "example": "example sentence using the word",
-      "difficulty_level": "B2/C1",
-      "relevance_reason": "why this word would help the user"
-    }}
-  ]
-}}
-
-Focus on words that would help the user express their ideas more precisely or naturally.
-"#,
-        req.user_input,
-        req.conversation_context
-            .unwrap_or_else(|| "No additional context".to_string())
-    );
+) -> Result<impl IntoResponse, AiError> {
+    let conversation_context = req
+        .conversation_context
+        .as_deref()
+        .unwrap_or("No additional context");
+    let level = learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    let [level, stretch_level] = level_vars(level);
+    let prompt = prompt::WORD_SUGGESTIONS.render(&[
+        PromptVar::user_input("user_input", &req.user_input),
+        PromptVar::user_input("context", conversation_context),
+        level,
+        stretch_level,
+    ]);
+
+    ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+    let known_words = load_known_words(&app_state, auth_user.user_id).await?;
+    let (mut response, cache_status, usage): (WordSuggestionsResponse, _, _) =
+        ai_usage::record_on_error(
+            &app_state,
+            auth_user.user_id,
+            AiEndpoint::WordSuggestions,
+            prompt.template,
+            ai_cache::generate_cached(&app_state, AiTask::Suggestions, &prompt, req.bypass_cache)
+                .await,
+        )
+        .await?;
+    ai_usage::record(
+        &app_state,
+        auth_user.user_id,
+        AiEndpoint::WordSuggestions,
+        prompt.template,
+        usage,
+        cache_status == CacheStatus::Hit,
+    )
+    .await;
+
+    mark_known(&mut response.suggestions, &known_words);
+
+    Ok((
+        StatusCode::OK,
+        ai_headers(&prompt, cache_status),
+        Json(response.suggestions),
+    ))
+}
 
-    let gemini_response = call_gemini_api(&app_state.gemini_api_key, &prompt).await?;
+async fn load_known_words(app_state: &AppState, user_id: Uuid) -> Result<KnownWords, AiError> {
+    KnownWords::load(&app_state.pool, user_id)
+        .await
+        .map_err(|e| AiError::Internal(e.to_string()))
+}
 
-    // Extract just the suggestions array from the response
-    let full_response: serde_json::Value = serde_json::from_str(&gemini_response).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to parse AI response: {}", e),
-        )
-    })?;
+// 登録済みの単語と同じ語 (変化形を含む) の提案に印を付ける
+// (キャッシュはユーザー間で共有するので、印はキャッシュから取り出した後で付ける)
+fn mark_known(suggestions: &mut [WordSuggestion], known_words: &KnownWords) {
+    for suggestion in suggestions {
+        let known = known_words.find(&suggestion.word);
+        suggestion.already_saved = known.is_some();
+        suggestion.existing_word_id = known.map(|word| word.id.clone());
+    }
+}
+
+// 提案をあとから単語として登録できるよう分析を保存する (失敗しても分析結果は返す)
+async fn save_analysis(
+    app_state: &AppState,
+    user_id: Uuid,
+    conversation_text: &str,
+    template: &PromptTemplate,
+    analysis: &ConversationAnalysisResponse,
+) -> Option<Uuid> {
+    let result = serde_json::to_value(analysis).unwrap_or_default();
+    match ConversationAnalysis::create(
+        &app_state.pool,
+        user_id,
+        conversation_text,
+        &result,
+        &template.id(),
+    )
+    .await
+    {
+        Ok(id) => Some(id),
+        Err(e) => {
+            println!("Failed to save conversation analysis: {}", e);
+            None
+        }
+    }
+}
+
+// キャッシュの利用結果と、使ったプロンプトの版
+pub(crate) fn ai_headers(prompt: &Prompt, status: CacheStatus) -> [(&'static str, String); 2] {
+    [
+        (CacheStatus::HEADER, status.as_str().to_string()),
+        (PromptTemplate::HEADER, prompt.template.id()),
+    ]
+}
+
+// POST /api/words/autofill - 見出し語 (と見かけた文) から単語カードの内容を提案
+pub async fn word_autofill_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<WordAutofillRequest>,
+) -> Result<impl IntoResponse, AiError> {
+    let headword = req.word.trim();
+    if headword.is_empty() {
+        return Err(AiError::BadRequest("word is required".to_string()));
+    }
+    let context_sentence = req
+        .context_sentence
+        .as_deref()
+        .map(str::trim)
+        .filter(|sentence| !sentence.is_empty())
+        .unwrap_or("(none)");
+    let prompt = prompt::WORD_AUTOFILL.render(&[
+        PromptVar::user_input("word", headword),
+        PromptVar::user_input("context", context_sentence),
+    ]);
+
+    ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+    let (autofill, cache_status, usage): (WordAutofill, _, _) = ai_usage::record_on_error(
+        &app_state,
+        auth_user.user_id,
+        AiEndpoint::WordAutofill,
+        prompt.template,
+        ai_cache::generate_cached(&app_state, AiTask::Generation, &prompt, req.bypass_cache).await,
+    )
+    .await?;
+    ai_usage::record(
+        &app_state,
+        auth_user.user_id,
+        AiEndpoint::WordAutofill,
+        prompt.template,
+        usage,
+        cache_status == CacheStatus::Hit,
+    )
+    .await;
+
+    let mut part_of_speech: Vec<String> = Vec::new();
+    for part in &autofill.part_of_speech {
+        let part = part.trim().to_lowercase();
+        if !part.is_empty() && !part_of_speech.contains(&part) {
+            part_of_speech.push(part);
+        }
+    }
+    let proposal = CreateWordRequest {
+        word: autofill.word.trim().to_string(),
+        meaning: autofill.meaning.trim().to_string(),
+        translation: Some(autofill.translation.trim().to_string()),
+        part_of_speech,
+        phonetic: Some(autofill.phonetic.trim().to_string()),
+        example: Some(autofill.example.trim().to_string()),
+        category: None,
+        synonyms: autofill
+            .synonyms
+            .iter()
+            .map(|synonym| synonym.trim().to_string())
+            .filter(|synonym| !synonym.is_empty())
+            .collect(),
+    };
+
+    Ok((
+        StatusCode::OK,
+        ai_headers(&prompt, cache_status),
+        Json(proposal),
+    ))
+}
 
-    let suggestions = full_response.get("suggestions")
-        .ok_or((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "No suggestions in AI response".to_string(),
-        ))?
-        .clone();
+const DEFAULT_EXAMPLE_COUNT: usize = 3;
+const MAX_EXAMPLE_COUNT: usize = 5;
+const MAX_EXAMPLE_TOPICS: usize = 3;
+// 例文に織り込んでもらう学習中の単語の数
+const REINFORCEMENT_WORD_LIMIT: i64 = 15;
 
-    Ok((StatusCode::OK, Json(suggestions)))
+// POST /api/words/:id/examples/generate - 学習者のレベルと話題に合わせた例文を生成
+pub async fn generate_examples_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<ExampleSentencesRequest>,
+) -> Result<impl IntoResponse, AiError> {
+    let word = Word::find_for_user(&app_state.pool, &id, auth_user.user_id)
+        .await
+        .map_err(|e| AiError::Internal(e.to_string()))?
+        .ok_or_else(|| AiError::NotFound("Word not found".to_string()))?;
+    let count = req
+        .count
+        .unwrap_or(DEFAULT_EXAMPLE_COUNT)
+        .clamp(1, MAX_EXAMPLE_COUNT);
+    let topics: Vec<&str> = req
+        .topics
+        .iter()
+        .map(|topic| topic.trim())
+        .filter(|topic| !topic.is_empty())
+        .take(MAX_EXAMPLE_TOPICS)
+        .collect();
+    let vocabulary = Word::study_headwords(
+        &app_state.pool,
+        auth_user.user_id,
+        &word.id,
+        REINFORCEMENT_WORD_LIMIT,
+    )
+    .await
+    .map_err(|e| AiError::Internal(e.to_string()))?;
+
+    let level = learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    let count_text = count.to_string();
+    let part_of_speech = word.part_of_speech.join(", ");
+    let topics_text = if topics.is_empty() {
+        "(any)".to_string()
+    } else {
+        topics.join(", ")
+    };
+    let vocabulary_text = if vocabulary.is_empty() {
+        "(none)".to_string()
+    } else {
+        vocabulary.join(", ")
+    };
+    let prompt = prompt::EXAMPLE_SENTENCES.render(&[
+        PromptVar::user_input("word", &word.word),
+        PromptVar::user_input("meaning", &word.meaning),
+        PromptVar::user_input("part_of_speech", &part_of_speech),
+        PromptVar::user_input("topics", &topics_text),
+        PromptVar::user_input("vocabulary", &vocabulary_text),
+        PromptVar::text("level", level.as_str()),
+        PromptVar::text("count", &count_text),
+    ]);
+
+    // 呼ぶたびに別の例文が欲しいのでキャッシュは使わない
+    ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+    let (response, usage): (ExampleSentencesResponse, _) = ai_usage::record_on_error(
+        &app_state,
+        auth_user.user_id,
+        AiEndpoint::ExampleSentences,
+        prompt.template,
+        generate_structured(app_state.llm.as_ref(), AiTask::Generation, &prompt.text).await,
+    )
+    .await?;
+    ai_usage::record(
+        &app_state,
+        auth_user.user_id,
+        AiEndpoint::ExampleSentences,
+        prompt.template,
+        usage,
+        false,
+    )
+    .await;
+
+    // 見出し語を使っていない例文は除き、学習中の単語を多く含む例文を先に並べる
+    let mut sentences: Vec<ExampleSentence> = response
+        .sentences
+        .into_iter()
+        .filter(|example| cloze::make_cloze(&example.sentence, &word.word).is_some())
+        .map(|mut example| {
+            example.reinforced_words = vocabulary
+                .iter()
+                .filter(|other| cloze::make_cloze(&example.sentence, other).is_some())
+                .cloned()
+                .collect();
+            example
+        })
+        .collect();
+    if sentences.is_empty() {
+        return Err(AiError::InvalidOutput {
+            reason: format!("no example sentence uses \"{}\"", word.word),
+            usage: TokenUsage::default(),
+        });
+    }
+    sentences.sort_by_key(|example| std::cmp::Reverse(example.reinforced_words.len()));
+
+    Ok((
+        StatusCode::OK,
+        [(PromptTemplate::HEADER, prompt.template.id())],
+        Json(sentences),
+    ))
 }
 
-// Error helper
-fn internal_error<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
-    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+// GET /api/ai/usage - 自分のAI使用量と上限
+pub async fn get_ai_usage_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AiError> {
+    let summary = ai_usage::summary(&app_state, auth_user.user_id)
+        .await
+        .map_err(|e| AiError::Internal(e.to_string()))?;
+
+    Ok((StatusCode::OK, Json(summary)))
 }
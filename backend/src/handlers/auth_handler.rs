@@ -28,6 +28,7 @@ pub struct OAuthResponse {
 #[derive(Debug, Deserialize)]
 struct GitHubTokenResponse {
     access_token: String,
+    #[allow(dead_code)]
     token_type: String,
 }
 
@@ -44,14 +45,18 @@ struct GitHubUser {
 struct GitHubEmail {
     email: String,
     primary: bool,
+    #[allow(dead_code)]
     verified: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct GoogleTokenResponse {
     access_token: String,
+    #[allow(dead_code)]
     expires_in: i64,
+    #[allow(dead_code)]
     token_type: String,
+    #[allow(dead_code)]
     id_token: Option<String>,
 }
 
@@ -61,6 +66,7 @@ struct GoogleUser {
     email: String,
     name: Option<String>,
     picture: Option<String>,
+    #[allow(dead_code)]
     verified_email: bool,
 }
 
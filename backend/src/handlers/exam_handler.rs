@@ -0,0 +1,433 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::auth_middleware::AuthUser;
+use crate::models::exam::{Exam, ExamQuestion, ExamStatus};
+use crate::models::word::{Word, WORD_COLUMNS};
+use crate::models::AppState;
+use crate::services::exam::{self, CHOICE_COUNT};
+
+const DEFAULT_QUESTION_COUNT: i32 = 20;
+const MAX_QUESTION_COUNT: i32 = 100;
+/// 制限時間の指定が無い場合の1問あたりの秒数
+const DEFAULT_SECONDS_PER_QUESTION: i32 = 30;
+const MAX_TIME_LIMIT_MINUTES: i32 = 180;
+const DEFAULT_HISTORY_LIMIT: i64 = 20;
+const MAX_HISTORY_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct StartExamRequest {
+    /// 出題するカテゴリ (省略・空なら全単語から)
+    pub categories: Option<Vec<String>>,
+    pub question_count: Option<i32>,
+    /// 制限時間 (分)。省略時は1問30秒で計算
+    pub time_limit_minutes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExamAnswerRequest {
+    /// 1始まりの問題番号
+    pub position: i32,
+    /// 0始まりの選択肢番号
+    pub choice: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExamHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExamQuestionView {
+    pub position: i32,
+    pub kind: String,
+    pub prompt: String,
+    pub choices: Vec<String>,
+    pub selected_index: Option<i32>,
+    /// 以下は試験終了後のみ
+    pub word_id: Option<String>,
+    pub correct_index: Option<i32>,
+    pub is_correct: Option<bool>,
+}
+
+impl ExamQuestionView {
+    fn new(question: ExamQuestion, reveal: bool) -> Self {
+        Self {
+            position: question.position,
+            kind: question.kind,
+            prompt: question.prompt,
+            choices: question.choices.0,
+            selected_index: question.selected_index,
+            word_id: reveal.then_some(question.word_id),
+            correct_index: reveal.then_some(question.correct_index),
+            is_correct: if reveal { question.is_correct } else { None },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExamSummary {
+    pub id: Uuid,
+    pub status: String,
+    pub categories: Vec<String>,
+    pub question_count: i32,
+    pub time_limit_seconds: i32,
+    pub score: Option<i32>,
+    pub percentage: Option<f64>,
+    pub started_at: DateTime<Utc>,
+    pub deadline_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// 受験中なら残り時間、終了後は None
+    pub remaining_seconds: Option<i64>,
+    /// 終了までにかかった時間
+    pub duration_seconds: Option<i64>,
+}
+
+impl From<&Exam> for ExamSummary {
+    fn from(exam: &Exam) -> Self {
+        let percentage = exam.score.map(|score| {
+            if exam.question_count > 0 {
+                (score as f64 * 1000.0 / exam.question_count as f64).round() / 10.0
+            } else {
+                0.0
+            }
+        });
+        let remaining_seconds = exam
+            .is_in_progress()
+            .then(|| (exam.deadline_at - Utc::now()).num_seconds().max(0));
+
+        Self {
+            id: exam.id,
+            status: exam.status.clone(),
+            categories: exam.categories.0.clone(),
+            question_count: exam.question_count,
+            time_limit_seconds: exam.time_limit_seconds,
+            score: exam.score,
+            percentage,
+            started_at: exam.started_at,
+            deadline_at: exam.deadline_at,
+            finished_at: exam.finished_at,
+            remaining_seconds,
+            duration_seconds: exam
+                .finished_at
+                .map(|finished| (finished - exam.started_at).num_seconds()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExamResponse {
+    #[serde(flatten)]
+    pub summary: ExamSummary,
+    pub questions: Vec<ExamQuestionView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExamAnswerResponse {
+    pub position: i32,
+    pub selected_index: i32,
+    pub answered: i64,
+    pub remaining_seconds: i64,
+}
+
+// POST /api/exams - 模擬試験を開始
+pub async fn start_exam_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<StartExamRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let question_count = payload.question_count.unwrap_or(DEFAULT_QUESTION_COUNT);
+    if !(1..=MAX_QUESTION_COUNT).contains(&question_count) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "question_count must be between 1 and {}",
+                MAX_QUESTION_COUNT
+            ),
+        ));
+    }
+    if payload
+        .time_limit_minutes
+        .is_some_and(|m| !(1..=MAX_TIME_LIMIT_MINUTES).contains(&m))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "time_limit_minutes must be between 1 and {}",
+                MAX_TIME_LIMIT_MINUTES
+            ),
+        ));
+    }
+
+    let categories: Vec<String> = payload
+        .categories
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+
+    // 誤答の選択肢はカテゴリに関係なく全単語から選ぶ
+    let sql = format!("SELECT {} FROM words WHERE user_id = $1", WORD_COLUMNS);
+    let pool = sqlx::query_as::<_, Word>(&sql)
+        .bind(auth_user.user_id)
+        .fetch_all(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    let candidates: Vec<Word> = pool
+        .iter()
+        .filter(|w| w.status != "suspended" && w.status != "ignored")
+        .filter(|w| {
+            categories.is_empty() || w.category.as_ref().is_some_and(|c| categories.contains(c))
+        })
+        .cloned()
+        .collect();
+
+    let questions = exam::build_exam(
+        &candidates,
+        &pool,
+        question_count as usize,
+        &mut rand::thread_rng(),
+    );
+    if questions.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Not enough words for an exam (at least {} words are needed for the choices)",
+                CHOICE_COUNT
+            ),
+        ));
+    }
+
+    // 単語が足りない場合は作れた問題数で実施する
+    let question_count = questions.len() as i32;
+    let time_limit_seconds = payload
+        .time_limit_minutes
+        .map(|m| m * 60)
+        .unwrap_or(question_count * DEFAULT_SECONDS_PER_QUESTION);
+    let started_at = Utc::now();
+    let deadline_at = started_at + Duration::seconds(time_limit_seconds as i64);
+
+    let mut tx = app_state.pool.begin().await.map_err(internal_error)?;
+
+    let exam = sqlx::query_as::<_, Exam>(
+        "INSERT INTO exams (id, user_id, categories, question_count, time_limit_seconds, status, started_at, deadline_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING *",
+    )
+    .bind(Uuid::new_v4())
+    .bind(auth_user.user_id)
+    .bind(serde_json::to_value(&categories).unwrap())
+    .bind(question_count)
+    .bind(time_limit_seconds)
+    .bind(ExamStatus::InProgress.as_str())
+    .bind(started_at)
+    .bind(deadline_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    for (i, question) in questions.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO exam_questions (exam_id, position, word_id, kind, prompt, choices, correct_index)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(exam.id)
+        .bind(i as i32 + 1)
+        .bind(&question.word_id)
+        .bind(question.kind.as_str())
+        .bind(&question.prompt)
+        .bind(serde_json::to_value(&question.choices).unwrap())
+        .bind(question.correct_index as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+    }
+
+    tx.commit().await.map_err(internal_error)?;
+
+    let response = exam_response(&app_state, exam).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// GET /api/exams - 過去の試験結果の一覧 (新しい順)
+pub async fn list_exams_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<ExamHistoryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .clamp(1, MAX_HISTORY_LIMIT);
+
+    let exams = sqlx::query_as::<_, Exam>(
+        "SELECT * FROM exams WHERE user_id = $1 ORDER BY started_at DESC LIMIT $2",
+    )
+    .bind(auth_user.user_id)
+    .bind(limit)
+    .fetch_all(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let mut summaries = Vec::with_capacity(exams.len());
+    for exam in exams {
+        let exam = exam
+            .expire_if_overdue(&app_state.pool)
+            .await
+            .map_err(internal_error)?;
+        summaries.push(ExamSummary::from(&exam));
+    }
+
+    Ok((StatusCode::OK, Json(summaries)))
+}
+
+// GET /api/exams/:id - 試験の状態 (終了後は問題ごとの結果を含むレポート)
+pub async fn get_exam_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let exam = find_exam(&app_state, id, auth_user.user_id).await?;
+    let exam = exam
+        .expire_if_overdue(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    let response = exam_response(&app_state, exam).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// POST /api/exams/:id/answers - 回答を記録 (終了までは変更可、正誤は終了後に公開)
+pub async fn answer_exam_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<ExamAnswerRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let exam = find_exam(&app_state, id, auth_user.user_id).await?;
+    let exam = exam
+        .expire_if_overdue(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    if !exam.is_in_progress() {
+        let message = if exam.status == ExamStatus::Expired.as_str() {
+            "Time limit exceeded"
+        } else {
+            "Exam is already finished"
+        };
+        return Err((StatusCode::CONFLICT, message.to_string()));
+    }
+    if !(0..CHOICE_COUNT as i32).contains(&payload.choice) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("choice must be between 0 and {}", CHOICE_COUNT - 1),
+        ));
+    }
+
+    let result = sqlx::query(
+        "UPDATE exam_questions SET
+            selected_index = $3,
+            is_correct = (correct_index = $3),
+            answered_at = NOW()
+         WHERE exam_id = $1 AND position = $2
+           AND EXISTS (SELECT 1 FROM exams WHERE id = $1 AND status = 'in_progress')",
+    )
+    .bind(exam.id)
+    .bind(payload.position)
+    .bind(payload.choice)
+    .execute(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Question not found".to_string()));
+    }
+
+    let (answered,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM exam_questions WHERE exam_id = $1 AND selected_index IS NOT NULL",
+    )
+    .bind(exam.id)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ExamAnswerResponse {
+            position: payload.position,
+            selected_index: payload.choice,
+            answered,
+            remaining_seconds: (exam.deadline_at - Utc::now()).num_seconds().max(0),
+        }),
+    ))
+}
+
+// POST /api/exams/:id/finish - 試験を終了して採点結果を返す
+pub async fn finish_exam_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let exam = find_exam(&app_state, id, auth_user.user_id).await?;
+
+    let status = if exam.is_overdue(Utc::now()) {
+        ExamStatus::Expired
+    } else {
+        ExamStatus::Finished
+    };
+    let exam = exam
+        .finalize(&app_state.pool, status)
+        .await
+        .map_err(internal_error)?;
+
+    let response = exam_response(&app_state, exam).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+async fn find_exam(
+    app_state: &AppState,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<Exam, (StatusCode, String)> {
+    Exam::find_for_user(&app_state.pool, id, user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Exam not found".to_string()))
+}
+
+// 問題を読み込んでレスポンスを組み立てる。正解は終了後のみ含める
+async fn exam_response(
+    app_state: &AppState,
+    exam: Exam,
+) -> Result<ExamResponse, (StatusCode, String)> {
+    let questions = exam
+        .questions(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+    let reveal = !exam.is_in_progress();
+
+    Ok(ExamResponse {
+        summary: ExamSummary::from(&exam),
+        questions: questions
+            .into_iter()
+            .map(|q| ExamQuestionView::new(q, reveal))
+            .collect(),
+    })
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::auth_middleware::AuthUser;
+use crate::models::daily_goal_result::DailyGoalResult;
+use crate::models::user_settings::UserSettings;
+use crate::models::AppState;
+use crate::services::streak::{self, DailyGoals, DayActivity};
+
+const MAX_FREEZES_PER_MONTH: i32 = 31;
+
+#[derive(Debug, Serialize)]
+pub struct GoalsResponse {
+    pub daily_new_words_goal: i32,
+    pub daily_reviews_goal: i32,
+    pub time_zone: String,
+    pub day_rollover_hour: i32,
+    pub streak_freezes_per_month: i32,
+}
+
+impl From<UserSettings> for GoalsResponse {
+    fn from(settings: UserSettings) -> Self {
+        Self {
+            daily_new_words_goal: settings.daily_new_words_goal,
+            daily_reviews_goal: settings.daily_reviews_goal,
+            time_zone: settings.time_zone,
+            day_rollover_hour: settings.day_rollover_hour,
+            streak_freezes_per_month: settings.streak_freezes_per_month,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateGoalsRequest {
+    pub daily_new_words_goal: Option<i32>,
+    pub daily_reviews_goal: Option<i32>,
+    pub time_zone: Option<String>,
+    pub day_rollover_hour: Option<i32>,
+    pub streak_freezes_per_month: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodayProgress {
+    pub date: NaiveDate,
+    pub new_words: i64,
+    pub reviews: i64,
+    pub daily_new_words_goal: i32,
+    pub daily_reviews_goal: i32,
+    pub goal_met: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreakResponse {
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub frozen_days: Vec<NaiveDate>,
+    pub freezes_used_this_month: u32,
+    pub freezes_remaining_this_month: u32,
+    pub today: TodayProgress,
+}
+
+// GET /api/goals - 1日の目標設定を取得
+pub async fn get_goals_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let settings = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(GoalsResponse::from(settings))))
+}
+
+// PUT /api/goals - 1日の目標設定を更新
+pub async fn update_goals_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<UpdateGoalsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if payload.daily_new_words_goal.is_some_and(|v| v < 0)
+        || payload.daily_reviews_goal.is_some_and(|v| v < 0)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Goals must not be negative".to_string(),
+        ));
+    }
+    if payload
+        .day_rollover_hour
+        .is_some_and(|h| !(0..=23).contains(&h))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "day_rollover_hour must be between 0 and 23".to_string(),
+        ));
+    }
+    if payload
+        .streak_freezes_per_month
+        .is_some_and(|v| !(0..=MAX_FREEZES_PER_MONTH).contains(&v))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "streak_freezes_per_month must be between 0 and {}",
+                MAX_FREEZES_PER_MONTH
+            ),
+        ));
+    }
+    if let Some(time_zone) = &payload.time_zone {
+        let (exists,): (bool,) =
+            sqlx::query_as("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+                .bind(time_zone)
+                .fetch_one(&app_state.pool)
+                .await
+                .map_err(internal_error)?;
+        if !exists {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown time zone: {}", time_zone),
+            ));
+        }
+    }
+
+    // 行が無ければデフォルトで作成してから更新する
+    let current = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    // 終わった日は変更前の目標とタイムゾーンで固定しておく (過去のストリークを変えない)
+    let today = current
+        .local_today(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+    DailyGoalResult::record_finished_days(&app_state.pool, &current, today)
+        .await
+        .map_err(internal_error)?;
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "UPDATE user_settings SET
+            daily_new_words_goal = COALESCE($2, daily_new_words_goal),
+            daily_reviews_goal = COALESCE($3, daily_reviews_goal),
+            time_zone = COALESCE($4, time_zone),
+            day_rollover_hour = COALESCE($5, day_rollover_hour),
+            streak_freezes_per_month = COALESCE($6, streak_freezes_per_month),
+            updated_at = NOW()
+         WHERE user_id = $1
+         RETURNING *",
+    )
+    .bind(auth_user.user_id)
+    .bind(payload.daily_new_words_goal)
+    .bind(payload.daily_reviews_goal)
+    .bind(&payload.time_zone)
+    .bind(payload.day_rollover_hour)
+    .bind(payload.streak_freezes_per_month)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(GoalsResponse::from(settings))))
+}
+
+// GET /api/streak - ストリークと今日の進捗 (記録ページ・トレイバッジ共通)
+pub async fn get_streak_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let settings = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+    let today = settings
+        .local_today(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    // 終わった日はその日の目標で判定し、今日だけ今の目標で判定する
+    DailyGoalResult::record_finished_days(&app_state.pool, &settings, today)
+        .await
+        .map_err(internal_error)?;
+    let mut met_days: BTreeSet<NaiveDate> =
+        DailyGoalResult::list_for_user(&app_state.pool, auth_user.user_id)
+            .await
+            .map_err(internal_error)?
+            .into_iter()
+            .filter(|result| result.goal_met())
+            .map(|result| result.day)
+            .collect();
+
+    // 今日の学習量をユーザーのローカル日付で集計
+    let (new_words, reviews): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*) FILTER (WHERE kind = 'word_added'),
+                COUNT(*) FILTER (WHERE kind = 'review')
+         FROM activity_log
+         WHERE user_id = $1
+           AND ((occurred_at AT TIME ZONE $2) - make_interval(hours => $3))::date = $4",
+    )
+    .bind(auth_user.user_id)
+    .bind(&settings.time_zone)
+    .bind(settings.day_rollover_hour)
+    .bind(today)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+    let today_activity = DayActivity { new_words, reviews };
+
+    let goals = DailyGoals {
+        new_words: settings.daily_new_words_goal as i64,
+        reviews: settings.daily_reviews_goal as i64,
+    };
+    if goals.is_met(&today_activity) {
+        met_days.insert(today);
+    }
+    let summary = streak::compute_streak(
+        &met_days,
+        today,
+        settings.streak_freezes_per_month.max(0) as u32,
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(StreakResponse {
+            current_streak: summary.current_streak,
+            longest_streak: summary.longest_streak,
+            frozen_days: summary.frozen_days,
+            freezes_used_this_month: summary.freezes_used_this_month,
+            freezes_remaining_this_month: summary.freezes_remaining_this_month,
+            today: TodayProgress {
+                date: today,
+                new_words: today_activity.new_words,
+                reviews: today_activity.reviews,
+                daily_new_words_goal: settings.daily_new_words_goal,
+                daily_reviews_goal: settings.daily_reviews_goal,
+                goal_met: goals.is_met(&today_activity),
+            },
+        }),
+    ))
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
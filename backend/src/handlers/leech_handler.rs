@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::auth_middleware::AuthUser;
+use crate::models::user_settings::UserSettings;
+use crate::models::word::{Word, WORD_COLUMNS};
+use crate::models::AppState;
+use crate::services::leech;
+
+#[derive(Debug, Serialize)]
+pub struct LeechSettingsResponse {
+    /// リーチとみなす忘却回数
+    pub threshold: i32,
+    /// リーチになった単語を自動で一時停止するか
+    pub auto_suspend: bool,
+}
+
+impl From<UserSettings> for LeechSettingsResponse {
+    fn from(settings: UserSettings) -> Self {
+        Self {
+            threshold: settings.leech_threshold,
+            auto_suspend: settings.leech_auto_suspend,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLeechSettingsRequest {
+    pub threshold: Option<i32>,
+    pub auto_suspend: Option<bool>,
+}
+
+// GET /api/leeches - リーチになっている単語の一覧
+pub async fn get_leeches_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let sql = format!(
+        "SELECT {} FROM words WHERE user_id = $1 AND is_leech ORDER BY lapses DESC, word",
+        WORD_COLUMNS
+    );
+    let words = sqlx::query_as::<_, Word>(&sql)
+        .bind(auth_user.user_id)
+        .fetch_all(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(words)))
+}
+
+// GET /api/leeches/settings - リーチ判定の設定を取得
+pub async fn get_leech_settings_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let settings = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(LeechSettingsResponse::from(settings))))
+}
+
+// PUT /api/leeches/settings - リーチ判定のしきい値・自動一時停止の設定
+pub async fn update_leech_settings_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<UpdateLeechSettingsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if payload.threshold.is_some_and(|t| t < 1) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "threshold must be at least 1".to_string(),
+        ));
+    }
+
+    UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "UPDATE user_settings SET
+            leech_threshold = COALESCE($2, leech_threshold),
+            leech_auto_suspend = COALESCE($3, leech_auto_suspend),
+            updated_at = NOW()
+         WHERE user_id = $1
+         RETURNING *",
+    )
+    .bind(auth_user.user_id)
+    .bind(payload.threshold)
+    .bind(payload.auto_suspend)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(LeechSettingsResponse::from(settings))))
+}
+
+// POST /api/words/:id/leech-aids - 覚え方・語源・対比例文をAIで(再)生成
+pub async fn regenerate_leech_aids_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let word = Word::find_for_user(&app_state.pool, &id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Word not found".to_string()))?;
+
+    let updated = leech::refresh_leech_aids(&app_state, &word).await?;
+
+    Ok((StatusCode::OK, Json(updated)))
+}
+
+// POST /api/words/:id/leech/reset - リーチの印と忘却回数をリセット
+pub async fn reset_leech_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // 自動で一時停止されていた単語は学習中に戻す
+    let sql = format!(
+        "UPDATE words SET
+            is_leech = false,
+            lapses = 0,
+            status = CASE WHEN is_leech AND status = 'suspended' THEN 'learning' ELSE status END,
+            updated_at = NOW()
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        WORD_COLUMNS
+    );
+    let word = sqlx::query_as::<_, Word>(&sql)
+        .bind(&id)
+        .bind(auth_user.user_id)
+        .fetch_optional(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    match word {
+        Some(word) => Ok((StatusCode::OK, Json(word))),
+        None => Err((StatusCode::NOT_FOUND, "Word not found".to_string())),
+    }
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
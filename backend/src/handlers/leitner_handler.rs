@@ -0,0 +1,212 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::auth_middleware::AuthUser;
+use crate::models::user_settings::UserSettings;
+use crate::models::word::Word;
+use crate::models::AppState;
+use crate::services::review;
+use crate::services::scheduler::{self, ReviewAnswer};
+
+#[derive(Debug, Serialize)]
+pub struct LeitnerSettingsResponse {
+    pub box_count: usize,
+    pub intervals: Vec<i32>,
+}
+
+impl From<UserSettings> for LeitnerSettingsResponse {
+    fn from(settings: UserSettings) -> Self {
+        Self {
+            box_count: settings.leitner_intervals.len(),
+            intervals: settings.leitner_intervals.0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLeitnerSettingsRequest {
+    /// 箱ごとの復習間隔 (日)。要素数が箱の数になる
+    pub intervals: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeitnerBox {
+    #[serde(rename = "box")]
+    pub box_number: i32,
+    pub interval_days: i32,
+    pub words: i64,
+    pub due: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeitnerBoxesResponse {
+    pub box_count: usize,
+    pub boxes: Vec<LeitnerBox>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeitnerAnswerRequest {
+    pub correct: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeitnerAnswerResponse {
+    pub word_id: String,
+    pub previous_box: i32,
+    #[serde(rename = "box")]
+    pub box_number: i32,
+    pub interval_days: i32,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+// GET /api/leitner/settings - ライトナー方式の設定を取得
+pub async fn get_leitner_settings_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let settings = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LeitnerSettingsResponse::from(settings)),
+    ))
+}
+
+// PUT /api/leitner/settings - 箱と間隔の設定
+pub async fn update_leitner_settings_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<UpdateLeitnerSettingsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if let Some(intervals) = &payload.intervals {
+        scheduler::validate_leitner_intervals(intervals)
+            .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+    }
+
+    UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    let intervals = payload
+        .intervals
+        .map(|intervals| serde_json::to_value(intervals).unwrap());
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "UPDATE user_settings SET
+            leitner_intervals = COALESCE($2, leitner_intervals),
+            updated_at = NOW()
+         WHERE user_id = $1
+         RETURNING *",
+    )
+    .bind(auth_user.user_id)
+    .bind(intervals)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LeitnerSettingsResponse::from(settings)),
+    ))
+}
+
+// GET /api/leitner/boxes - 箱ごとの単語数と復習待ちの数
+pub async fn get_leitner_boxes_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let settings = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+    let intervals = settings.leitner_intervals.0;
+    let box_count = intervals.len().max(1) as i32;
+
+    // 箱の数を減らした場合、溢れた単語は最後の箱に数える (一時停止・対象外の単語は数えない)
+    let rows: Vec<(i32, i64, i64)> = sqlx::query_as(
+        "SELECT LEAST(GREATEST(leitner_box, 1), $2) AS box,
+                COUNT(*),
+                COUNT(*) FILTER (WHERE due_at IS NULL OR due_at <= NOW())
+         FROM words
+         WHERE user_id = $1 AND status NOT IN ('suspended', 'ignored')
+         GROUP BY box",
+    )
+    .bind(auth_user.user_id)
+    .bind(box_count)
+    .fetch_all(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let boxes = (1..=box_count)
+        .map(|box_number| {
+            let (words, due) = rows
+                .iter()
+                .find(|(b, _, _)| *b == box_number)
+                .map(|(_, words, due)| (*words, *due))
+                .unwrap_or((0, 0));
+            LeitnerBox {
+                box_number,
+                interval_days: scheduler::leitner_interval(&intervals, box_number),
+                words,
+                due,
+            }
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(LeitnerBoxesResponse {
+            box_count: box_count as usize,
+            boxes,
+        }),
+    ))
+}
+
+// POST /api/leitner/words/:id/answer - 回答結果で単語を昇格・降格させる
+pub async fn leitner_answer_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<LeitnerAnswerRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let word = Word::find_for_user(&app_state.pool, &id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Word not found".to_string()))?;
+
+    let updated = review::record_review(
+        &app_state,
+        auth_user.user_id,
+        &word,
+        ReviewAnswer {
+            correct: payload.correct,
+        },
+    )
+    .await
+    .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LeitnerAnswerResponse {
+            word_id: updated.id,
+            previous_box: word.leitner_box,
+            box_number: updated.leitner_box,
+            interval_days: updated.interval_days,
+            due_at: updated.due_at,
+        }),
+    ))
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
@@ -1,3 +1,11 @@
 pub mod word_handler;
 pub mod auth_handler;
 pub mod ai_handler;
+pub mod quiz_handler;
+pub mod goal_handler;
+pub mod leitner_handler;
+pub mod study_handler;
+pub mod leech_handler;
+pub mod exam_handler;
+pub mod placement_handler;
+pub mod tutor_handler;
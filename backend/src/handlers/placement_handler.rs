@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::auth_middleware::AuthUser;
+use crate::models::placement::{PlacementItemRow, PlacementTest};
+use crate::models::user::User;
+use crate::models::AppState;
+use crate::services::placement::{self, AnsweredItem, ItemKind};
+
+#[derive(Debug, Deserialize)]
+pub struct PlacementAnswerRequest {
+    pub position: i32,
+    /// はい/いいえ問題の回答 (その単語を知っているか)
+    pub known: Option<bool>,
+    /// 4択問題の回答 (0始まり)
+    pub choice: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlacementItemView {
+    pub position: i32,
+    pub kind: String,
+    pub word: String,
+    /// 4択問題の選択肢 (はい/いいえ問題では空)
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlacementResultView {
+    pub cefr_level: String,
+    pub vocab_estimate: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlacementTestResponse {
+    pub id: Uuid,
+    pub status: String,
+    pub answered: usize,
+    /// 次に答える問題 (終了後は None)
+    pub current_item: Option<PlacementItemView>,
+    /// 推定結果 (終了後のみ)
+    pub result: Option<PlacementResultView>,
+}
+
+// POST /api/placement - レベル判定テストを開始し、最初の問題を返す
+pub async fn start_placement_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let test = sqlx::query_as::<_, PlacementTest>(
+        "INSERT INTO placement_tests (user_id) VALUES ($1) RETURNING *",
+    )
+    .bind(auth_user.user_id)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    advance(&app_state, &test, &[]).await?;
+
+    let response = placement_response(&app_state, test.id, auth_user.user_id).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// GET /api/placement/:id - テストの進行状況 (終了後は推定結果)
+pub async fn get_placement_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let response = placement_response(&app_state, id, auth_user.user_id).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// POST /api/placement/:id/answers - 現在の問題に回答し、次の問題 (または結果) を返す
+pub async fn answer_placement_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<PlacementAnswerRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let test = PlacementTest::find_for_user(&app_state.pool, id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Placement test not found".to_string(),
+        ))?;
+    if !test.is_in_progress() {
+        return Err((
+            StatusCode::CONFLICT,
+            "Placement test is already finished".to_string(),
+        ));
+    }
+
+    let items = test.items(&app_state.pool).await.map_err(internal_error)?;
+    let current = items
+        .last()
+        .filter(|item| item.answered_at.is_none() && item.position == payload.position)
+        .ok_or((
+            StatusCode::CONFLICT,
+            "Only the current item can be answered".to_string(),
+        ))?;
+
+    let correct = match ItemKind::parse(&current.kind) {
+        Some(ItemKind::YesNo) => {
+            let known = payload.known.ok_or((
+                StatusCode::BAD_REQUEST,
+                "known is required for yes/no items".to_string(),
+            ))?;
+            known == current.is_real
+        }
+        Some(ItemKind::MultipleChoice) => {
+            let choice = payload.choice.ok_or((
+                StatusCode::BAD_REQUEST,
+                "choice is required for multiple-choice items".to_string(),
+            ))?;
+            Some(choice) == current.correct_index
+        }
+        None => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unknown placement item kind: {}", current.kind),
+            ))
+        }
+    };
+
+    // 同時に回答された場合は先に記録された方を採用する
+    let result = sqlx::query(
+        "UPDATE placement_items SET correct = $3, answered_at = NOW()
+         WHERE test_id = $1 AND position = $2 AND answered_at IS NULL",
+    )
+    .bind(test.id)
+    .bind(current.position)
+    .bind(correct)
+    .execute(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            "This item has already been answered".to_string(),
+        ));
+    }
+
+    let history: Vec<AnsweredItem> = items
+        .iter()
+        .map(|item| AnsweredItem {
+            band: item.band.max(0) as usize,
+            word: item.word.clone(),
+            is_real: item.is_real,
+            correct: if item.position == current.position {
+                correct
+            } else {
+                item.correct.unwrap_or(false)
+            },
+        })
+        .collect();
+
+    advance(&app_state, &test, &history).await?;
+
+    let response = placement_response(&app_state, test.id, auth_user.user_id).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// 次の問題を出すか、境界が定まっていれば結果を確定してプロフィールに保存する
+async fn advance(
+    app_state: &AppState,
+    test: &PlacementTest,
+    history: &[AnsweredItem],
+) -> Result<(), (StatusCode, String)> {
+    let progress = placement::progress(history);
+    let next = if progress.finished {
+        None
+    } else {
+        placement::next_item(history, progress, &mut rand::thread_rng())
+    };
+
+    match next {
+        Some(item) => {
+            sqlx::query(
+                "INSERT INTO placement_items (test_id, position, band, kind, word, is_real, choices, correct_index)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            )
+            .bind(test.id)
+            .bind(history.len() as i32 + 1)
+            .bind(item.band as i32)
+            .bind(item.kind.as_str())
+            .bind(&item.word)
+            .bind(item.is_real)
+            .bind(serde_json::to_value(&item.choices).unwrap())
+            .bind(item.correct_index.map(|i| i as i32))
+            .execute(&app_state.pool)
+            .await
+            .map_err(internal_error)?;
+        }
+        None => {
+            let result = placement::estimate(history);
+            sqlx::query(
+                "UPDATE placement_tests SET
+                    status = 'finished', cefr_level = $2, vocab_estimate = $3, finished_at = NOW()
+                 WHERE id = $1",
+            )
+            .bind(test.id)
+            .bind(result.cefr_level.as_str())
+            .bind(result.vocab_estimate as i32)
+            .execute(&app_state.pool)
+            .await
+            .map_err(internal_error)?;
+
+            User::update_level(
+                &app_state.pool,
+                test.user_id,
+                result.cefr_level,
+                result.vocab_estimate as i32,
+            )
+            .await
+            .map_err(internal_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn placement_response(
+    app_state: &AppState,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<PlacementTestResponse, (StatusCode, String)> {
+    let test = PlacementTest::find_for_user(&app_state.pool, id, user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Placement test not found".to_string(),
+        ))?;
+    let items = test.items(&app_state.pool).await.map_err(internal_error)?;
+
+    let answered = items
+        .iter()
+        .filter(|item| item.answered_at.is_some())
+        .count();
+    let current_item = items
+        .into_iter()
+        .find(|item| item.answered_at.is_none())
+        .filter(|_| test.is_in_progress())
+        .map(|item: PlacementItemRow| PlacementItemView {
+            position: item.position,
+            kind: item.kind,
+            word: item.word,
+            choices: item.choices.0,
+        });
+    let result = match (test.cefr_level, test.vocab_estimate) {
+        (Some(cefr_level), Some(vocab_estimate)) => Some(PlacementResultView {
+            cefr_level,
+            vocab_estimate,
+        }),
+        _ => None,
+    };
+
+    Ok(PlacementTestResponse {
+        id: test.id,
+        status: test.status,
+        answered,
+        current_item,
+        result,
+    })
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
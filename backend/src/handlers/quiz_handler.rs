@@ -0,0 +1,570 @@
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::auth_middleware::AuthUser;
+use crate::llm::prompt::{self, PromptVar};
+use crate::llm::structured::require_text;
+use crate::llm::{generate_structured, AiTask, StructuredOutput, TokenUsage};
+use crate::models::activity::{Activity, ActivityKind};
+use crate::models::cloze_item::ClozeItem;
+use crate::models::word::{Word, WORD_COLUMNS};
+use crate::models::AppState;
+use crate::services::ai_usage::{self, AiEndpoint};
+use crate::services::cloze::{self, Cloze};
+use crate::services::grader::{self, GradeOutcome, MatchField, Verdict};
+use crate::services::review;
+use crate::services::scheduler::ReviewAnswer;
+
+const DEFAULT_CLOZE_COUNT: i64 = 10;
+const MAX_CLOZE_COUNT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct GradeAnswerRequest {
+    pub word_id: String,
+    pub answer: String,
+    /// 曖昧な回答をAIに判定させるか (デフォルト: true)
+    pub use_ai: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradedBy {
+    Local,
+    Ai,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GradeAnswerResponse {
+    pub correct: bool,
+    pub verdict: Verdict,
+    pub score: f64,
+    pub confidence: f64,
+    pub graded_by: GradedBy,
+    pub matched_field: Option<MatchField>,
+    pub matched_text: Option<String>,
+    pub feedback: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiGradeResult {
+    correct: bool,
+    feedback: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClozeRequest {
+    /// 出題する単語 (省略時はランダム)
+    pub word_ids: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub count: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClozeExercise {
+    pub id: Uuid,
+    pub word_id: String,
+    pub text: String,
+    pub blanks: usize,
+    pub hint: String,
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClozeAnswerRequest {
+    pub answer: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClozeAnswerResponse {
+    pub correct: bool,
+    pub wrong_form: bool,
+    pub expected: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiExampleSentence {
+    sentence: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SentenceCheckRequest {
+    pub word_id: String,
+    /// 学習者が目標の単語を使って書いた文
+    pub sentence: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentenceCorrection {
+    pub original: String,
+    pub corrected: String,
+    pub explanation: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiSentenceCheck {
+    meaning_correct: bool,
+    grammar_correct: bool,
+    #[serde(default)]
+    corrections: Vec<SentenceCorrection>,
+    natural_rewrite: String,
+    feedback: String,
+}
+
+impl StructuredOutput for AiGradeResult {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "correct": { "type": "boolean" },
+                "feedback": { "type": "string" }
+            },
+            "required": ["correct", "feedback"]
+        })
+    }
+}
+
+impl StructuredOutput for AiExampleSentence {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": { "sentence": { "type": "string" } },
+            "required": ["sentence"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require_text("sentence", &self.sentence)
+    }
+}
+
+impl StructuredOutput for AiSentenceCheck {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "meaning_correct": { "type": "boolean" },
+                "grammar_correct": { "type": "boolean" },
+                "corrections": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "original": { "type": "string" },
+                            "corrected": { "type": "string" },
+                            "explanation": { "type": "string" }
+                        },
+                        "required": ["original", "corrected", "explanation"]
+                    }
+                },
+                "natural_rewrite": { "type": "string" },
+                "feedback": { "type": "string" }
+            },
+            "required": ["meaning_correct", "grammar_correct", "corrections", "natural_rewrite", "feedback"]
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SentenceCheckResponse {
+    pub correct: bool,
+    /// 文中に目標の単語 (変化形を含む) が使われているか
+    pub uses_word: bool,
+    /// 単語が正しい意味で使われているか
+    pub meaning_correct: bool,
+    /// 単語の形・語法を含めて文法的に正しいか
+    pub grammar_correct: bool,
+    pub corrections: Vec<SentenceCorrection>,
+    pub natural_rewrite: Option<String>,
+    pub feedback: String,
+}
+
+// POST /api/quiz/grade - 回答の採点 (ローカル採点を優先し、曖昧な場合のみAIに問い合わせる)
+pub async fn grade_answer_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<GradeAnswerRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let word = Word::find_for_user(&app_state.pool, &req.word_id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Word not found".to_string()))?;
+
+    let outcome = grader::grade_answer(&word, &req.answer);
+
+    let mut response = GradeAnswerResponse {
+        correct: outcome.is_probably_correct(),
+        verdict: outcome.verdict,
+        score: outcome.score,
+        confidence: outcome.confidence,
+        graded_by: GradedBy::Local,
+        matched_field: outcome.matched_field,
+        matched_text: outcome.matched_text.clone(),
+        feedback: None,
+    };
+
+    if outcome.verdict == Verdict::Ambiguous && req.use_ai.unwrap_or(true) {
+        // AIが使えない・失敗した場合はローカルの判定をそのまま返す
+        match grade_with_ai(&app_state, &word, &req.answer, &outcome).await {
+            Ok(ai_result) => {
+                response.correct = ai_result.correct;
+                response.verdict = if ai_result.correct {
+                    Verdict::Correct
+                } else {
+                    Verdict::Incorrect
+                };
+                response.graded_by = GradedBy::Ai;
+                response.feedback = Some(ai_result.feedback);
+            }
+            Err((status, message)) => {
+                println!("AI grading skipped ({}): {}", status, message);
+            }
+        }
+    }
+
+    review::record_review(
+        &app_state,
+        auth_user.user_id,
+        &word,
+        ReviewAnswer {
+            correct: response.correct,
+        },
+    )
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+async fn grade_with_ai(
+    app_state: &AppState,
+    word: &Word,
+    answer: &str,
+    outcome: &GradeOutcome,
+) -> Result<AiGradeResult, (StatusCode, String)> {
+    let score = format!("{:.2}", outcome.score);
+    let synonyms = word.synonyms.join(", ");
+    let prompt = prompt::ANSWER_GRADING.render(&[
+        PromptVar::user_input("word", &word.word),
+        PromptVar::user_input("meaning", &word.meaning),
+        PromptVar::user_input(
+            "translation",
+            word.translation.as_deref().unwrap_or("(none)"),
+        ),
+        PromptVar::user_input("synonyms", &synonyms),
+        PromptVar::user_input("answer", answer),
+        PromptVar::text("score", &score),
+    ]);
+
+    // 上限に達していればAIを使わずローカルの判定を返す
+    ai_usage::check_quota(app_state, word.user_id).await?;
+    let (result, usage) = ai_usage::record_on_error(
+        app_state,
+        word.user_id,
+        AiEndpoint::AnswerGrading,
+        prompt.template,
+        generate_structured(app_state.llm.as_ref(), AiTask::Grading, &prompt.text).await,
+    )
+    .await?;
+    ai_usage::record(
+        app_state,
+        word.user_id,
+        AiEndpoint::AnswerGrading,
+        prompt.template,
+        usage,
+        false,
+    )
+    .await;
+    Ok(result)
+}
+
+// POST /api/quiz/sentence - 目標の単語を使った作文をAIで添削し、結果を復習として記録
+pub async fn check_sentence_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<SentenceCheckRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let sentence = req.sentence.trim();
+    if sentence.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "sentence is required".to_string()));
+    }
+
+    let word = Word::find_for_user(&app_state.pool, &req.word_id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Word not found".to_string()))?;
+
+    // 単語自体が使われていなければAIに問い合わせずに不正解とする
+    let response = if cloze::make_cloze(sentence, &word.word).is_none() {
+        SentenceCheckResponse {
+            correct: false,
+            uses_word: false,
+            meaning_correct: false,
+            grammar_correct: false,
+            corrections: Vec::new(),
+            natural_rewrite: None,
+            feedback: format!("The sentence does not use \"{}\".", word.word),
+        }
+    } else {
+        ai_usage::check_quota(&app_state, auth_user.user_id).await?;
+        let (result, usage) = check_sentence_with_ai(&app_state, &word, sentence).await?;
+        ai_usage::record(
+            &app_state,
+            auth_user.user_id,
+            AiEndpoint::SentenceCheck,
+            &prompt::SENTENCE_CHECK,
+            usage,
+            false,
+        )
+        .await;
+        SentenceCheckResponse {
+            correct: result.meaning_correct && result.grammar_correct,
+            uses_word: true,
+            meaning_correct: result.meaning_correct,
+            grammar_correct: result.grammar_correct,
+            corrections: result.corrections,
+            natural_rewrite: Some(result.natural_rewrite),
+            feedback: result.feedback,
+        }
+    };
+
+    review::record_review(
+        &app_state,
+        auth_user.user_id,
+        &word,
+        ReviewAnswer {
+            correct: response.correct,
+        },
+    )
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+async fn check_sentence_with_ai(
+    app_state: &AppState,
+    word: &Word,
+    sentence: &str,
+) -> Result<(AiSentenceCheck, TokenUsage), (StatusCode, String)> {
+    let part_of_speech = word.part_of_speech.join(", ");
+    let prompt = prompt::SENTENCE_CHECK.render(&[
+        PromptVar::user_input("word", &word.word),
+        PromptVar::user_input("meaning", &word.meaning),
+        PromptVar::user_input("part_of_speech", &part_of_speech),
+        PromptVar::user_input("sentence", sentence),
+    ]);
+
+    let result = ai_usage::record_on_error(
+        app_state,
+        word.user_id,
+        AiEndpoint::SentenceCheck,
+        prompt.template,
+        generate_structured(app_state.llm.as_ref(), AiTask::Grading, &prompt.text).await,
+    )
+    .await?;
+    Ok(result)
+}
+
+// POST /api/quiz/cloze - 例文から穴埋め問題を生成
+pub async fn create_cloze_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<ClozeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let count = req
+        .count
+        .unwrap_or(DEFAULT_CLOZE_COUNT)
+        .clamp(1, MAX_CLOZE_COUNT);
+
+    let sql = format!(
+        "SELECT {} FROM words
+         WHERE user_id = $1
+           AND status NOT IN ('suspended', 'ignored')
+           AND ($2::text IS NULL OR category = $2)
+           AND ($3::text[] IS NULL OR id = ANY($3))
+         ORDER BY random() LIMIT $4",
+        WORD_COLUMNS
+    );
+    let words = sqlx::query_as::<_, Word>(&sql)
+        .bind(auth_user.user_id)
+        .bind(&req.category)
+        .bind(&req.word_ids)
+        .bind(count)
+        .fetch_all(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    let mut exercises = Vec::new();
+    for word in words {
+        // 例文が複数あれば毎回違う文から出題する
+        let mut examples = word.example_sentences();
+        examples.shuffle(&mut rand::thread_rng());
+        let from_example = examples
+            .into_iter()
+            .find_map(|example| cloze::make_cloze(example, &word.word));
+
+        let (generated, source) = match from_example {
+            Some(generated) => (generated, "example"),
+            None => match generate_ai_cloze(&app_state, &word).await {
+                Ok(Some(generated)) => (generated, "ai"),
+                Ok(None) => continue,
+                Err((status, message)) => {
+                    println!("AI example generation skipped ({}): {}", status, message);
+                    continue;
+                }
+            },
+        };
+
+        let item = sqlx::query_as::<_, ClozeItem>(
+            "INSERT INTO cloze_items (user_id, word_id, headword, text, answers, source)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+        )
+        .bind(auth_user.user_id)
+        .bind(&word.id)
+        .bind(&word.word)
+        .bind(&generated.text)
+        .bind(serde_json::to_value(&generated.answers).unwrap())
+        .bind(source)
+        .fetch_one(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+        exercises.push(ClozeExercise {
+            id: item.id,
+            word_id: item.word_id,
+            text: item.text,
+            blanks: item.answers.len(),
+            hint: word.translation.clone().unwrap_or(word.meaning.clone()),
+            source: item.source,
+        });
+    }
+
+    Ok((StatusCode::OK, Json(exercises)))
+}
+
+// POST /api/quiz/cloze/:id/check - 穴埋め問題の採点
+pub async fn check_cloze_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<ClozeAnswerRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let item =
+        sqlx::query_as::<_, ClozeItem>("SELECT * FROM cloze_items WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(auth_user.user_id)
+            .fetch_optional(&app_state.pool)
+            .await
+            .map_err(internal_error)?
+            .ok_or((
+                StatusCode::NOT_FOUND,
+                "Cloze exercise not found".to_string(),
+            ))?;
+
+    let exercise = Cloze {
+        text: item.text,
+        answers: item.answers.0,
+    };
+    let result = cloze::check_answer(&exercise, &item.headword, &req.answer);
+
+    // 問題を作った後に単語が削除されていたら学習ログだけ残す
+    let answer = ReviewAnswer {
+        correct: result.correct,
+    };
+    match Word::find_for_user(&app_state.pool, &item.word_id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(word) => {
+            review::record_review(&app_state, auth_user.user_id, &word, answer)
+                .await
+                .map_err(internal_error)?;
+        }
+        None => {
+            Activity::record(
+                &app_state.pool,
+                auth_user.user_id,
+                ActivityKind::Review,
+                Some(&item.word_id),
+                Some(result.correct),
+            )
+            .await
+            .map_err(internal_error)?;
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ClozeAnswerResponse {
+            correct: result.correct,
+            wrong_form: result.wrong_form,
+            expected: exercise.answers.join(" "),
+        }),
+    ))
+}
+
+// 例文が無い・例文から単語が見つからない場合にAIで例文を作る
+async fn generate_ai_cloze(
+    app_state: &AppState,
+    word: &Word,
+) -> Result<Option<Cloze>, (StatusCode, String)> {
+    let prompt = prompt::CLOZE_SENTENCE.render(&[
+        PromptVar::user_input("word", &word.word),
+        PromptVar::user_input("meaning", &word.meaning),
+    ]);
+
+    ai_usage::check_quota(app_state, word.user_id).await?;
+    let (generated, usage): (AiExampleSentence, _) = ai_usage::record_on_error(
+        app_state,
+        word.user_id,
+        AiEndpoint::ClozeSentence,
+        prompt.template,
+        generate_structured(app_state.llm.as_ref(), AiTask::Generation, &prompt.text).await,
+    )
+    .await?;
+    ai_usage::record(
+        app_state,
+        word.user_id,
+        AiEndpoint::ClozeSentence,
+        prompt.template,
+        usage,
+        false,
+    )
+    .await;
+
+    let Some(result) = cloze::make_cloze(&generated.sentence, &word.word) else {
+        return Ok(None);
+    };
+
+    // 例文が未登録なら生成した文を保存しておく
+    if word.example.as_deref().is_none_or(|e| e.trim().is_empty()) {
+        sqlx::query(
+            "UPDATE words SET example = $1, updated_at = $2 WHERE id = $3 AND user_id = $4",
+        )
+        .bind(&generated.sentence)
+        .bind(chrono::Utc::now())
+        .bind(&word.id)
+        .bind(word.user_id)
+        .execute(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+    }
+
+    Ok(Some(result))
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::auth_middleware::AuthUser;
+use crate::models::study_plan::{StudyPlan, StudyPlanItem};
+use crate::models::user_settings::UserSettings;
+use crate::models::word::{Word, WORD_COLUMNS};
+use crate::models::AppState;
+use crate::services::forecast::{self, ForecastDay};
+use crate::services::planner::{self, PlanItemKind};
+use crate::services::scheduler::ReviewState;
+
+const DEFAULT_FORECAST_DAYS: usize = 30;
+const MIN_FORECAST_DAYS: usize = 30;
+const MAX_FORECAST_DAYS: usize = 90;
+const MAX_SIMULATED_NEW_WORDS_PER_DAY: u32 = 200;
+
+#[derive(Debug, Serialize)]
+pub struct StudySettingsResponse {
+    pub max_new_words_per_day: i32,
+    pub max_reviews_per_day: i32,
+}
+
+impl From<UserSettings> for StudySettingsResponse {
+    fn from(settings: UserSettings) -> Self {
+        Self {
+            max_new_words_per_day: settings.max_new_words_per_day,
+            max_reviews_per_day: settings.max_reviews_per_day,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateStudySettingsRequest {
+    pub max_new_words_per_day: Option<i32>,
+    pub max_reviews_per_day: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    /// 予測する日数 (30-90)
+    pub days: Option<usize>,
+    /// 毎日追加すると仮定する新規単語の数
+    pub new_words_per_day: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForecastDayResponse {
+    pub date: NaiveDate,
+    pub reviews: u32,
+    pub new_words: u32,
+    /// 新規単語を追加しなかった場合の復習数
+    pub baseline_reviews: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForecastResponse {
+    pub days: usize,
+    pub new_words_per_day: u32,
+    pub max_reviews_per_day: i32,
+    pub total_reviews: u32,
+    pub average_reviews_per_day: f64,
+    pub peak: Option<ForecastDay>,
+    /// 復習数が1日の上限を超える日数
+    pub days_over_limit: usize,
+    pub daily: Vec<ForecastDayResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StudyPlanItemResponse {
+    pub position: i32,
+    pub kind: String,
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub word: Word,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StudyPlanResponse {
+    pub date: NaiveDate,
+    pub total: usize,
+    pub completed: usize,
+    pub remaining: usize,
+    pub new_words: usize,
+    pub reviews: usize,
+    /// 次に学習する項目の位置 (すべて終わっていれば None)
+    pub next_position: Option<i32>,
+    pub items: Vec<StudyPlanItemResponse>,
+}
+
+// GET /api/study/today - 今日の学習プラン (無ければ作成)。進捗は復習の記録時に更新される
+pub async fn get_today_plan_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let pool = &app_state.pool;
+    let settings = UserSettings::get_or_create(pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+    let today = settings.local_today(pool).await.map_err(internal_error)?;
+
+    let plan = match StudyPlan::find_for_date(pool, auth_user.user_id, today)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(plan) => plan,
+        None => create_plan(&app_state, &settings, today).await?,
+    };
+
+    let items = plan.items(pool).await.map_err(internal_error)?;
+    let word_ids: Vec<String> = items.iter().map(|item| item.word_id.clone()).collect();
+    let sql = format!(
+        "SELECT {} FROM words WHERE user_id = $1 AND id = ANY($2)",
+        WORD_COLUMNS
+    );
+    let words: HashMap<String, Word> = sqlx::query_as::<_, Word>(&sql)
+        .bind(auth_user.user_id)
+        .bind(&word_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|word| (word.id.clone(), word))
+        .collect();
+
+    Ok((StatusCode::OK, Json(plan_response(today, items, words))))
+}
+
+// GET /api/study/forecast - 今後30-90日の復習数の予測 (新規単語を毎日N語追加した場合も)
+pub async fn get_forecast_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<ForecastQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let days = query.days.unwrap_or(DEFAULT_FORECAST_DAYS);
+    if !(MIN_FORECAST_DAYS..=MAX_FORECAST_DAYS).contains(&days) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "days must be between {} and {}",
+                MIN_FORECAST_DAYS, MAX_FORECAST_DAYS
+            ),
+        ));
+    }
+    let new_words_per_day = query.new_words_per_day.unwrap_or(0);
+    if new_words_per_day > MAX_SIMULATED_NEW_WORDS_PER_DAY {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "new_words_per_day must be at most {}",
+                MAX_SIMULATED_NEW_WORDS_PER_DAY
+            ),
+        ));
+    }
+
+    let pool = &app_state.pool;
+    let settings = UserSettings::get_or_create(pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+    let today = settings.local_today(pool).await.map_err(internal_error)?;
+
+    // 期限をユーザーのローカル日付に直し、今日からの日数で取得する
+    let rows: Vec<(i32, i32, i32, i32, i32)> = sqlx::query_as(
+        "SELECT (((due_at AT TIME ZONE $2) - make_interval(hours => $3))::date - $4::date),
+                interval_days, repetitions, lapses, leitner_box
+         FROM words
+         WHERE user_id = $1 AND due_at IS NOT NULL
+           AND status IN ('learning', 'reviewing', 'mastered')",
+    )
+    .bind(auth_user.user_id)
+    .bind(&settings.time_zone)
+    .bind(settings.day_rollover_hour)
+    .bind(today)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+
+    let cards: Vec<(i64, ReviewState)> = rows
+        .into_iter()
+        .map(
+            |(offset, interval_days, repetitions, lapses, leitner_box)| {
+                (
+                    offset as i64,
+                    ReviewState {
+                        interval_days,
+                        repetitions,
+                        lapses,
+                        leitner_box,
+                    },
+                )
+            },
+        )
+        .collect();
+
+    let intervals = &settings.leitner_intervals;
+    let projected = forecast::simulate(&cards, today, days, new_words_per_day, intervals);
+    let baseline = if new_words_per_day > 0 {
+        forecast::simulate(&cards, today, days, 0, intervals)
+    } else {
+        projected.clone()
+    };
+
+    let total_reviews: u32 = projected.iter().map(|day| day.reviews).sum();
+    let peak = projected
+        .iter()
+        .copied()
+        .filter(|day| day.reviews > 0)
+        .max_by_key(|day| (day.reviews, std::cmp::Reverse(day.date)));
+    let days_over_limit = projected
+        .iter()
+        .filter(|day| day.reviews as i64 > settings.max_reviews_per_day as i64)
+        .count();
+
+    Ok((
+        StatusCode::OK,
+        Json(ForecastResponse {
+            days,
+            new_words_per_day,
+            max_reviews_per_day: settings.max_reviews_per_day,
+            total_reviews,
+            average_reviews_per_day: (total_reviews as f64 * 10.0 / days as f64).round() / 10.0,
+            peak,
+            days_over_limit,
+            daily: projected
+                .iter()
+                .zip(baseline.iter())
+                .map(|(day, base)| ForecastDayResponse {
+                    date: day.date,
+                    reviews: day.reviews,
+                    new_words: day.new_words,
+                    baseline_reviews: base.reviews,
+                })
+                .collect(),
+        }),
+    ))
+}
+
+// GET /api/study/settings - 学習プランの1日の上限を取得
+pub async fn get_study_settings_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let settings = UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(StudySettingsResponse::from(settings))))
+}
+
+// PUT /api/study/settings - 新規単語・復習の1日の上限を更新 (翌日のプランから反映)
+pub async fn update_study_settings_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<UpdateStudySettingsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if payload.max_new_words_per_day.is_some_and(|v| v < 0)
+        || payload.max_reviews_per_day.is_some_and(|v| v < 0)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Daily limits must not be negative".to_string(),
+        ));
+    }
+
+    UserSettings::get_or_create(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "UPDATE user_settings SET
+            max_new_words_per_day = COALESCE($2, max_new_words_per_day),
+            max_reviews_per_day = COALESCE($3, max_reviews_per_day),
+            updated_at = NOW()
+         WHERE user_id = $1
+         RETURNING *",
+    )
+    .bind(auth_user.user_id)
+    .bind(payload.max_new_words_per_day)
+    .bind(payload.max_reviews_per_day)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(StudySettingsResponse::from(settings))))
+}
+
+// 期限の来た復習と新規単語から今日のプランを作って保存する
+async fn create_plan(
+    app_state: &AppState,
+    settings: &UserSettings,
+    today: NaiveDate,
+) -> Result<StudyPlan, (StatusCode, String)> {
+    let pool = &app_state.pool;
+
+    let sql = format!(
+        "SELECT {} FROM words
+         WHERE user_id = $1 AND status IN ('learning', 'reviewing', 'mastered')
+           AND due_at <= NOW()
+         ORDER BY due_at
+         LIMIT $2",
+        WORD_COLUMNS
+    );
+    let reviews = sqlx::query_as::<_, Word>(&sql)
+        .bind(settings.user_id)
+        .bind(settings.max_reviews_per_day.max(0) as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(internal_error)?;
+
+    let sql = format!(
+        "SELECT {} FROM words
+         WHERE user_id = $1 AND status = 'new'
+         ORDER BY created_at
+         LIMIT $2",
+        WORD_COLUMNS
+    );
+    let new_words = sqlx::query_as::<_, Word>(&sql)
+        .bind(settings.user_id)
+        .bind(settings.max_new_words_per_day.max(0) as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(internal_error)?;
+
+    let entries = planner::build_plan(&reviews, &new_words);
+
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+
+    // 別の端末が同時に作成していた場合はそちらを使う
+    let plan = sqlx::query_as::<_, StudyPlan>(
+        "INSERT INTO study_plans (user_id, plan_date) VALUES ($1, $2)
+         ON CONFLICT (user_id, plan_date) DO NOTHING
+         RETURNING *",
+    )
+    .bind(settings.user_id)
+    .bind(today)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    let Some(plan) = plan else {
+        tx.rollback().await.map_err(internal_error)?;
+        return StudyPlan::find_for_date(pool, settings.user_id, today)
+            .await
+            .map_err(internal_error)?
+            .ok_or((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Study plan disappeared".to_string(),
+            ));
+    };
+
+    for (i, entry) in entries.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO study_plan_items (plan_id, position, word_id, kind) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(plan.id)
+        .bind(i as i32 + 1)
+        .bind(&entry.word_id)
+        .bind(entry.kind.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+    }
+
+    tx.commit().await.map_err(internal_error)?;
+
+    Ok(plan)
+}
+
+// 削除された単語の項目は除いてレスポンスを組み立てる
+fn plan_response(
+    date: NaiveDate,
+    items: Vec<StudyPlanItem>,
+    mut words: HashMap<String, Word>,
+) -> StudyPlanResponse {
+    let items: Vec<StudyPlanItemResponse> = items
+        .into_iter()
+        .filter_map(|item| {
+            let word = words.remove(&item.word_id)?;
+            Some(StudyPlanItemResponse {
+                position: item.position,
+                kind: item.kind,
+                completed: item.completed_at.is_some(),
+                completed_at: item.completed_at,
+                word,
+            })
+        })
+        .collect();
+
+    let completed = items.iter().filter(|item| item.completed).count();
+    let new_words = items
+        .iter()
+        .filter(|item| item.kind == PlanItemKind::New.as_str())
+        .count();
+
+    StudyPlanResponse {
+        date,
+        total: items.len(),
+        completed,
+        remaining: items.len() - completed,
+        new_words,
+        reviews: items.len() - new_words,
+        next_position: items
+            .iter()
+            .find(|item| !item.completed)
+            .map(|item| item.position),
+        items,
+    }
+}
+
+// 内部エラーを統一的に扱うためのヘルパー関数
+fn internal_error<E>(err: E) -> (StatusCode, String)
+where
+    E: std::error::Error,
+{
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
@@ -0,0 +1,441 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use shuttle_axum::axum::{
+    extract::{Extension, Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::auth_middleware::AuthUser;
+use crate::handlers::ai_handler;
+use crate::llm::prompt::{self, PromptTemplate, PromptVar};
+use crate::llm::structured::require_text;
+use crate::llm::{generate_structured, AiError, AiTask, StructuredOutput};
+use crate::models::conversation_analysis::ConversationAnalysis;
+use crate::models::tutor_session::{TutorMessage, TutorRole, TutorSession};
+use crate::models::user::CefrLevel;
+use crate::models::word::Word;
+use crate::models::AppState;
+use crate::services::ai_usage::{self, AiEndpoint};
+use crate::services::cloze;
+
+/// 会話に織り込んでもらう復習予定の単語の数
+const FOCUS_WORD_LIMIT: i64 = 8;
+/// チューターに渡す直近の発言の数 (長い会話でプロンプトが膨らみすぎないように)
+const PROMPT_HISTORY_LIMIT: usize = 30;
+const MAX_MESSAGE_CHARS: usize = 2000;
+const DEFAULT_SESSION_LIMIT: i64 = 20;
+const MAX_SESSION_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct StartTutorSessionRequest {
+    /// 会話の話題 (省略時はチューターに任せる)
+    pub topic: Option<String>,
+    /// CEFR レベル (B2 など)。省略時はレベル判定テストの結果を使う
+    pub user_level: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TutorMessageRequest {
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TutorSessionListQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TutorSessionResponse {
+    #[serde(flatten)]
+    pub session: TutorSession,
+    pub messages: Vec<TutorMessage>,
+    /// 終了時の会話分析 (終了後のみ)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TutorTurnResponse {
+    /// 保存した学習者の発言
+    pub message: TutorMessage,
+    /// チューターの返答
+    pub reply: TutorMessage,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TutorReply {
+    reply: String,
+}
+
+impl StructuredOutput for TutorReply {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": { "reply": { "type": "string" } },
+            "required": ["reply"]
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require_text("reply", &self.reply)
+    }
+}
+
+// POST /api/tutor/sessions - チューターとの会話を始める (復習予定の単語を織り込む)
+pub async fn start_tutor_session_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<StartTutorSessionRequest>,
+) -> Result<impl IntoResponse, AiError> {
+    let level =
+        ai_handler::learner_level(&app_state, auth_user.user_id, req.user_level.as_deref()).await?;
+    let topic = req
+        .topic
+        .as_deref()
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty());
+    let focus_words = Word::due_headwords(&app_state.pool, auth_user.user_id, FOCUS_WORD_LIMIT)
+        .await
+        .map_err(db_error)?;
+
+    // 最初の発言を生成できてからセッションを作る
+    let (opening, used_words) = generate_reply(
+        &app_state,
+        auth_user.user_id,
+        level.as_str(),
+        topic,
+        &focus_words,
+        &[],
+        None,
+    )
+    .await?;
+
+    let mut tx = app_state.pool.begin().await.map_err(db_error)?;
+    let session = TutorSession::create(
+        &mut tx,
+        auth_user.user_id,
+        topic,
+        level.as_str(),
+        &focus_words,
+    )
+    .await
+    .map_err(db_error)?;
+    let message = session
+        .add_message(&mut tx, TutorRole::Tutor, &opening, &used_words)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(PromptTemplate::HEADER, prompt::TUTOR_CHAT.id())],
+        Json(TutorSessionResponse {
+            session,
+            messages: vec![message],
+            analysis: None,
+        }),
+    ))
+}
+
+// GET /api/tutor/sessions - 会話の一覧 (新しい順)
+pub async fn list_tutor_sessions_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<TutorSessionListQuery>,
+) -> Result<impl IntoResponse, AiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SESSION_LIMIT)
+        .clamp(1, MAX_SESSION_LIMIT);
+    let sessions = TutorSession::list_for_user(&app_state.pool, auth_user.user_id, limit)
+        .await
+        .map_err(db_error)?;
+
+    Ok((StatusCode::OK, Json(sessions)))
+}
+
+// GET /api/tutor/sessions/:id - 会話の履歴 (終了していれば会話分析も)
+pub async fn get_tutor_session_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AiError> {
+    let session = find_session(&app_state, id, auth_user.user_id).await?;
+    let response = session_response(&app_state, session).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// POST /api/tutor/sessions/:id/messages - 学習者の発言を送り、チューターの返答を受け取る
+pub async fn send_tutor_message_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<TutorMessageRequest>,
+) -> Result<impl IntoResponse, AiError> {
+    let content = req.content.trim();
+    if content.is_empty() {
+        return Err(AiError::BadRequest("content is required".to_string()));
+    }
+    if content.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(AiError::BadRequest(format!(
+            "content must be at most {} characters",
+            MAX_MESSAGE_CHARS
+        )));
+    }
+
+    let session = find_session(&app_state, id, auth_user.user_id).await?;
+    if !session.is_active() {
+        return Err(session_ended());
+    }
+    let history = session.messages(&app_state.pool).await.map_err(db_error)?;
+
+    // 返答を生成できてから両方の発言を保存する (失敗しても履歴が食い違わないように)
+    let (reply, reply_words) = generate_reply(
+        &app_state,
+        auth_user.user_id,
+        &session.cefr_level,
+        session.topic.as_deref(),
+        &session.focus_words,
+        &history,
+        Some(content),
+    )
+    .await?;
+
+    // 生成の間は行をロックしない。保存の直前に短くロックし、会話が進んでいれば 409 にする
+    let mut tx = app_state.pool.begin().await.map_err(db_error)?;
+    let session = lock_session(&mut tx, id, auth_user.user_id).await?;
+    if !session.is_active() {
+        return Err(session_ended());
+    }
+    ensure_unchanged(&mut tx, &session, &history).await?;
+    let message = session
+        .add_message(
+            &mut tx,
+            TutorRole::Learner,
+            content,
+            &words_used_in(content, &session.focus_words),
+        )
+        .await
+        .map_err(db_error)?;
+    let reply = session
+        .add_message(&mut tx, TutorRole::Tutor, &reply, &reply_words)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        [(PromptTemplate::HEADER, prompt::TUTOR_CHAT.id())],
+        Json(TutorTurnResponse { message, reply }),
+    ))
+}
+
+// POST /api/tutor/sessions/:id/end - 会話を終了し、書き起こし全体を会話分析にかける
+pub async fn end_tutor_session_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AiError> {
+    let session = find_session(&app_state, id, auth_user.user_id).await?;
+    // 終了済みなら保存してある分析をそのまま返す
+    if !session.is_active() {
+        let response = session_response(&app_state, session).await?;
+        return Ok((StatusCode::OK, Json(response)).into_response());
+    }
+
+    let messages = session.messages(&app_state.pool).await.map_err(db_error)?;
+    if !messages
+        .iter()
+        .any(|m| m.role == TutorRole::Learner.as_str())
+    {
+        return Err(AiError::BadRequest(
+            "Send at least one message before ending the session".to_string(),
+        ));
+    }
+
+    let level = CefrLevel::parse(&session.cefr_level).unwrap_or(CefrLevel::B2);
+    let (analysis, prompt, cache_status) = ai_handler::analyze_conversation(
+        &app_state,
+        auth_user.user_id,
+        &transcript(&messages, None),
+        level,
+        false,
+    )
+    .await?;
+
+    // 分析の間は行をロックしない。同時に終了された場合は先に保存された分析を返す
+    let mut tx = app_state.pool.begin().await.map_err(db_error)?;
+    let session = lock_session(&mut tx, id, auth_user.user_id).await?;
+    if !session.is_active() {
+        tx.rollback().await.map_err(db_error)?;
+        let response = session_response(&app_state, session).await?;
+        return Ok((StatusCode::OK, Json(response)).into_response());
+    }
+    ensure_unchanged(&mut tx, &session, &messages).await?;
+    let session = session
+        .end(&mut tx, analysis.analysis_id)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        ai_handler::ai_headers(&prompt, cache_status),
+        Json(TutorSessionResponse {
+            session,
+            messages,
+            analysis: Some(serde_json::to_value(&analysis).unwrap_or_default()),
+        }),
+    )
+        .into_response())
+}
+
+// チューターの次の発言と、その中で使われた復習予定の単語
+async fn generate_reply(
+    app_state: &AppState,
+    user_id: Uuid,
+    level: &str,
+    topic: Option<&str>,
+    focus_words: &[String],
+    history: &[TutorMessage],
+    learner_message: Option<&str>,
+) -> Result<(String, Vec<String>), AiError> {
+    let focus_text = if focus_words.is_empty() {
+        "(none)".to_string()
+    } else {
+        focus_words.join(", ")
+    };
+    let recent = &history[history.len().saturating_sub(PROMPT_HISTORY_LIMIT)..];
+    let transcript = transcript(recent, learner_message);
+    let transcript = if transcript.is_empty() {
+        "(no messages yet)".to_string()
+    } else {
+        transcript
+    };
+    let prompt = prompt::TUTOR_CHAT.render(&[
+        PromptVar::text("level", level),
+        PromptVar::user_input("topic", topic.unwrap_or("(free conversation)")),
+        PromptVar::user_input("focus_words", &focus_text),
+        PromptVar::user_input("transcript", &transcript),
+    ]);
+
+    ai_usage::check_quota(app_state, user_id).await?;
+    let (reply, usage): (TutorReply, _) = ai_usage::record_on_error(
+        app_state,
+        user_id,
+        AiEndpoint::TutorChat,
+        prompt.template,
+        generate_structured(app_state.llm.as_ref(), AiTask::Tutor, &prompt.text).await,
+    )
+    .await?;
+    ai_usage::record(
+        app_state,
+        user_id,
+        AiEndpoint::TutorChat,
+        prompt.template,
+        usage,
+        false,
+    )
+    .await;
+
+    let reply = reply.reply.trim().to_string();
+    let used_words = words_used_in(&reply, focus_words);
+    Ok((reply, used_words))
+}
+
+// "Tutor: ..." / "Learner: ..." の形の書き起こし
+fn transcript(messages: &[TutorMessage], learner_message: Option<&str>) -> String {
+    let mut lines: Vec<String> = messages
+        .iter()
+        .map(|message| {
+            let speaker = TutorRole::parse(&message.role).unwrap_or(TutorRole::Tutor);
+            format!("{}: {}", speaker.speaker(), message.content)
+        })
+        .collect();
+    if let Some(content) = learner_message {
+        lines.push(format!("{}: {}", TutorRole::Learner.speaker(), content));
+    }
+    lines.join("\n")
+}
+
+// 発言に含まれる単語 (変化形を含む)
+fn words_used_in(text: &str, words: &[String]) -> Vec<String> {
+    words
+        .iter()
+        .filter(|word| cloze::make_cloze(text, word).is_some())
+        .cloned()
+        .collect()
+}
+
+async fn find_session(
+    app_state: &AppState,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<TutorSession, AiError> {
+    TutorSession::find_for_user(&app_state.pool, id, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AiError::NotFound("Session not found".to_string()))
+}
+
+async fn lock_session(
+    conn: &mut PgConnection,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<TutorSession, AiError> {
+    TutorSession::lock_for_user(conn, id, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AiError::NotFound("Session not found".to_string()))
+}
+
+// ロックを取った後で、読み込んだ履歴の後に発言が増えていないか確かめる
+async fn ensure_unchanged(
+    conn: &mut PgConnection,
+    session: &TutorSession,
+    history: &[TutorMessage],
+) -> Result<(), AiError> {
+    let expected = history.last().map_or(0, |message| message.position);
+    let last_position = session.last_position(conn).await.map_err(db_error)?;
+    if last_position != expected {
+        return Err(AiError::Conflict(
+            "The session received another message in the meantime; reload it and try again"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn session_ended() -> AiError {
+    AiError::BadRequest("This session has already ended".to_string())
+}
+
+// 発言の履歴と、終了していれば保存した会話分析を読み込む
+async fn session_response(
+    app_state: &AppState,
+    session: TutorSession,
+) -> Result<TutorSessionResponse, AiError> {
+    let messages = session.messages(&app_state.pool).await.map_err(db_error)?;
+    let analysis = match session.analysis_id {
+        Some(analysis_id) => {
+            ConversationAnalysis::find_result(&app_state.pool, analysis_id, session.user_id)
+                .await
+                .map_err(db_error)?
+        }
+        None => None,
+    };
+
+    Ok(TutorSessionResponse {
+        session,
+        messages,
+        analysis,
+    })
+}
+
+fn db_error(err: sqlx::Error) -> AiError {
+    AiError::Internal(err.to_string())
+}
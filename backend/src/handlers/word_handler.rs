@@ -1,17 +1,22 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use shuttle_axum::axum::{
-    extract::{Extension, Json, Path, State},
-    http::StatusCode,
+    extract::{Extension, Json, Path, Query, State},
+    http::{header, StatusCode},
     response::IntoResponse,
 };
 use uuid::Uuid;
 
 use crate::auth_middleware::AuthUser;
-use crate::models::word::Word;
+use crate::handlers::ai_handler::{ConversationAnalysisResponse, WordSuggestion};
+use crate::models::activity::{Activity, ActivityKind};
+use crate::models::conversation_analysis::ConversationAnalysis;
+use crate::models::user::CefrLevel;
+use crate::models::word::{Word, WordSource, WordSourceMetadata, WordStatus, WORD_COLUMNS};
 use crate::models::AppState;
+use crate::services::known_words::KnownWords;
 
 // Request/Response DTOs
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateWordRequest {
     pub word: String,
     pub meaning: String,
@@ -20,6 +25,19 @@ pub struct CreateWordRequest {
     pub phonetic: Option<String>,
     pub example: Option<String>,
     pub category: Option<String>,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+}
+
+// AIの提案を単語として登録するリクエスト (提案そのものか、保存済みの会話分析の提案を指定する)
+#[derive(Debug, Deserialize)]
+pub struct SaveSuggestionRequest {
+    pub suggestion: Option<WordSuggestion>,
+    pub analysis_id: Option<Uuid>,
+    /// analysis_id の分析の何番目の提案か (0始まり、デフォルト: 0)
+    pub suggestion_index: Option<usize>,
+    pub translation: Option<String>,
+    pub category: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,24 +48,195 @@ pub struct UpdateWordRequest {
     pub part_of_speech: Option<Vec<String>>,
     pub phonetic: Option<String>,
     pub example: Option<String>,
+    /// 追加の例文 (置き換える)
+    pub examples: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub synonyms: Option<Vec<String>>,
+}
+
+// 生成した例文を単語の例文に加えるリクエスト
+#[derive(Debug, Deserialize)]
+pub struct AddExampleRequest {
+    pub sentence: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWordStatusRequest {
+    pub status: WordStatus,
+}
+
+// 一覧・エクスポート・統計で共通のクエリパラメータ
+#[derive(Debug, Deserialize)]
+pub struct WordFilterQuery {
+    /// カンマ区切りのステータス (例: "learning,reviewing")
+    pub status: Option<String>,
+    /// エクスポート形式 (json | csv)
+    pub format: Option<String>,
+}
+
+impl WordFilterQuery {
+    /// SQL にバインドするステータスの配列 (指定なしなら None)
+    fn statuses(&self) -> Result<Option<Vec<String>>, (StatusCode, String)> {
+        match &self.status {
+            Some(value) => {
+                let statuses = WordStatus::parse_list(value)
+                    .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+                Ok(Some(
+                    statuses.iter().map(|s| s.as_str().to_string()).collect(),
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusCount {
+    pub status: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryCount {
     pub category: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WordStatsResponse {
+    pub total: i64,
+    pub due_now: i64,
+    pub by_status: Vec<StatusCount>,
+    pub by_category: Vec<CategoryCount>,
 }
 
 // GET /api/words - ユーザーの単語一覧取得
 pub async fn get_words_handler(
     State(app_state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<WordFilterQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let statuses = query.statuses()?;
+    let words = fetch_words(&app_state, auth_user.user_id, statuses).await?;
+
+    Ok((StatusCode::OK, Json(words)))
+}
+
+// GET /api/words/export - 単語のエクスポート (JSON / CSV)
+pub async fn export_words_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<WordFilterQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let statuses = query.statuses()?;
+    let words = fetch_words(&app_state, auth_user.user_id, statuses).await?;
+
+    let (content_type, file_name, body) = match query.format.as_deref().unwrap_or("json") {
+        "json" => (
+            "application/json",
+            "lexiflow-words.json",
+            serde_json::to_string_pretty(&words).map_err(internal_error)?,
+        ),
+        "csv" => (
+            "text/csv; charset=utf-8",
+            "lexiflow-words.csv",
+            words_to_csv(&words),
+        ),
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unsupported export format: {}", other),
+            ))
+        }
+    };
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        body,
+    ))
+}
+
+// GET /api/words/stats - ステータス・カテゴリ別の単語数
+pub async fn get_word_stats_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<WordFilterQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let words = sqlx::query_as::<_, Word>(
-        "SELECT id, word, meaning, translation, part_of_speech, phonetic, example, category, user_id, created_at, updated_at
-         FROM words WHERE user_id = $1 ORDER BY created_at DESC"
+    let statuses = query.statuses()?;
+
+    let by_status: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT status, COUNT(*) FROM words
+         WHERE user_id = $1 AND ($2::text[] IS NULL OR status = ANY($2))
+         GROUP BY status",
     )
     .bind(auth_user.user_id)
+    .bind(&statuses)
     .fetch_all(&app_state.pool)
     .await
     .map_err(internal_error)?;
 
-    Ok((StatusCode::OK, Json(words)))
+    let by_category: Vec<(Option<String>, i64)> = sqlx::query_as(
+        "SELECT category, COUNT(*) FROM words
+         WHERE user_id = $1 AND ($2::text[] IS NULL OR status = ANY($2))
+         GROUP BY category
+         ORDER BY COUNT(*) DESC",
+    )
+    .bind(auth_user.user_id)
+    .bind(&statuses)
+    .fetch_all(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    // 一時停止・対象外の単語は復習待ちに数えない
+    let (due_now,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM words
+         WHERE user_id = $1 AND ($2::text[] IS NULL OR status = ANY($2))
+           AND status NOT IN ('suspended', 'ignored')
+           AND due_at IS NOT NULL AND due_at <= NOW()",
+    )
+    .bind(auth_user.user_id)
+    .bind(&statuses)
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    // 0件のステータスも含めて定義順に並べる
+    let by_status: Vec<StatusCount> = WordStatus::ALL
+        .iter()
+        .filter(|status| {
+            statuses
+                .as_ref()
+                .is_none_or(|list| list.iter().any(|s| s == status.as_str()))
+        })
+        .map(|status| StatusCount {
+            status: status.as_str().to_string(),
+            count: by_status
+                .iter()
+                .find(|(s, _)| s == status.as_str())
+                .map(|(_, count)| *count)
+                .unwrap_or(0),
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(WordStatsResponse {
+            total: by_status.iter().map(|s| s.count).sum(),
+            due_now,
+            by_status,
+            by_category: by_category
+                .into_iter()
+                .map(|(category, count)| CategoryCount { category, count })
+                .collect(),
+        }),
+    ))
 }
 
 // GET /api/words/:id - 特定の単語詳細取得
@@ -56,15 +245,9 @@ pub async fn get_word_handler(
     Path(id): Path<String>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let word = sqlx::query_as::<_, Word>(
-        "SELECT id, word, meaning, translation, part_of_speech, phonetic, example, category, user_id, created_at, updated_at
-         FROM words WHERE id = $1 AND user_id = $2"
-    )
-    .bind(&id)
-    .bind(auth_user.user_id)
-    .fetch_optional(&app_state.pool)
-    .await
-    .map_err(internal_error)?;
+    let word = Word::find_for_user(&app_state.pool, &id, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
 
     match word {
         Some(word) => Ok((StatusCode::OK, Json(word))),
@@ -78,29 +261,87 @@ pub async fn create_word_handler(
     Extension(auth_user): Extension<AuthUser>,
     Json(payload): Json<CreateWordRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    // cuidの代わりにuuidを使用（文字列として）
-    let id = Uuid::new_v4().to_string();
-    let now = chrono::Utc::now();
+    let word = insert_word(
+        &app_state,
+        auth_user.user_id,
+        &payload,
+        WordSource::Manual,
+        None,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(word)))
+}
+
+// POST /api/words/from-suggestion - AIの提案をそのまま単語として登録
+pub async fn create_word_from_suggestion_handler(
+    State(app_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<SaveSuggestionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let suggestion = match (payload.suggestion, payload.analysis_id) {
+        (Some(suggestion), _) => suggestion,
+        (None, Some(analysis_id)) => {
+            find_analysis_suggestion(
+                &app_state,
+                auth_user.user_id,
+                analysis_id,
+                payload.suggestion_index.unwrap_or(0),
+            )
+            .await?
+        }
+        (None, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "suggestion or analysis_id is required".to_string(),
+            ))
+        }
+    };
+    if suggestion.word.trim().is_empty() || suggestion.meaning.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "suggestion must have a word and a meaning".to_string(),
+        ));
+    }
+
+    // 同じ見出し語が登録済みなら二重に登録しない
+    // (変化形の一致は別の語のこともあるので、提案の already_saved で知らせるだけにする)
+    let known_words = KnownWords::load(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(internal_error)?;
+    if let Some(known) = known_words.find_exact(&suggestion.word) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("\"{}\" is already saved (id: {})", known.word, known.id),
+        ));
+    }
 
-    let word = sqlx::query_as::<_, Word>(
-        "INSERT INTO words (id, word, meaning, translation, part_of_speech, phonetic, example, category, user_id, created_at, updated_at)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-         RETURNING id, word, meaning, translation, part_of_speech, phonetic, example, category, user_id, created_at, updated_at"
+    let example = suggestion.example.trim();
+    let word = CreateWordRequest {
+        word: suggestion.word.trim().to_string(),
+        meaning: suggestion.meaning.trim().to_string(),
+        translation: payload.translation,
+        part_of_speech: split_part_of_speech(&suggestion.part_of_speech),
+        phonetic: None,
+        example: (!example.is_empty()).then(|| example.to_string()),
+        category: payload.category,
+        synonyms: Vec::new(),
+    };
+    let metadata = WordSourceMetadata {
+        difficulty_level: CefrLevel::parse(&suggestion.difficulty_level)
+            .map(|level| level.as_str().to_string()),
+        relevance_reason: Some(suggestion.relevance_reason.trim().to_string())
+            .filter(|reason| !reason.is_empty()),
+        analysis_id: payload.analysis_id,
+    };
+    let word = insert_word(
+        &app_state,
+        auth_user.user_id,
+        &word,
+        WordSource::AiSuggestion,
+        Some(metadata),
     )
-    .bind(&id)
-    .bind(&payload.word)
-    .bind(&payload.meaning)
-    .bind(&payload.translation)
-    .bind(serde_json::to_value(&payload.part_of_speech).unwrap())
-    .bind(&payload.phonetic)
-    .bind(&payload.example)
-    .bind(&payload.category)
-    .bind(auth_user.user_id)
-    .bind(now)
-    .bind(now)
-    .fetch_one(&app_state.pool)
-    .await
-    .map_err(internal_error)?;
+    .await?;
 
     Ok((StatusCode::CREATED, Json(word)))
 }
@@ -142,10 +383,18 @@ pub async fn update_word_handler(
         set_clauses.push(format!("example = ${}", bind_count));
         bind_count += 1;
     }
+    if payload.examples.is_some() {
+        set_clauses.push(format!("examples = ${}", bind_count));
+        bind_count += 1;
+    }
     if payload.category.is_some() {
         set_clauses.push(format!("category = ${}", bind_count));
         bind_count += 1;
     }
+    if payload.synonyms.is_some() {
+        set_clauses.push(format!("synonyms = ${}", bind_count));
+        bind_count += 1;
+    }
 
     if set_clauses.is_empty() {
         return Err((StatusCode::BAD_REQUEST, "No fields to update".to_string()));
@@ -154,9 +403,9 @@ pub async fn update_word_handler(
     set_clauses.push(format!("updated_at = ${}", bind_count));
 
     let sql = format!(
-        "UPDATE words SET {} WHERE id = $1 AND user_id = $2
-         RETURNING id, word, meaning, translation, part_of_speech, phonetic, example, category, user_id, created_at, updated_at",
-        set_clauses.join(", ")
+        "UPDATE words SET {} WHERE id = $1 AND user_id = $2 RETURNING {}",
+        set_clauses.join(", "),
+        WORD_COLUMNS
     );
 
     let mut query = sqlx::query_as::<_, Word>(&sql)
@@ -181,9 +430,15 @@ pub async fn update_word_handler(
     if let Some(example) = payload.example {
         query = query.bind(example);
     }
+    if let Some(examples) = payload.examples {
+        query = query.bind(serde_json::to_value(examples).unwrap());
+    }
     if let Some(category) = payload.category {
         query = query.bind(category);
     }
+    if let Some(synonyms) = payload.synonyms {
+        query = query.bind(serde_json::to_value(synonyms).unwrap());
+    }
 
     query = query.bind(now);
 
@@ -198,6 +453,71 @@ pub async fn update_word_handler(
     }
 }
 
+// POST /api/words/:id/examples - 例文を追加 (例文が未設定ならそれを例文にする)
+pub async fn add_example_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<AddExampleRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let sentence = payload.sentence.trim();
+    if sentence.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "sentence is required".to_string()));
+    }
+
+    // 右辺の example / examples は更新前の値
+    let sql = format!(
+        "UPDATE words SET
+            example = COALESCE(example, $3),
+            examples = CASE
+                WHEN example IS NULL OR example = $3 OR examples ? $3 THEN examples
+                ELSE examples || jsonb_build_array($3::text)
+            END,
+            updated_at = NOW()
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        WORD_COLUMNS
+    );
+    let word = sqlx::query_as::<_, Word>(&sql)
+        .bind(&id)
+        .bind(auth_user.user_id)
+        .bind(sentence)
+        .fetch_optional(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    match word {
+        Some(word) => Ok((StatusCode::OK, Json(word))),
+        None => Err((StatusCode::NOT_FOUND, "Word not found".to_string())),
+    }
+}
+
+// PUT /api/words/:id/status - 学習ステータスを手動で変更
+pub async fn update_word_status_handler(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<UpdateWordStatusRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let sql = format!(
+        "UPDATE words SET status = $3, updated_at = $4 WHERE id = $1 AND user_id = $2 RETURNING {}",
+        WORD_COLUMNS
+    );
+    let word = sqlx::query_as::<_, Word>(&sql)
+        .bind(&id)
+        .bind(auth_user.user_id)
+        .bind(payload.status.as_str())
+        .bind(chrono::Utc::now())
+        .fetch_optional(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    match word {
+        Some(word) => Ok((StatusCode::OK, Json(word))),
+        None => Err((StatusCode::NOT_FOUND, "Word not found".to_string())),
+    }
+}
+
 // DELETE /api/words/:id - 単語削除
 pub async fn delete_word_handler(
     State(app_state): State<AppState>,
@@ -218,6 +538,143 @@ pub async fn delete_word_handler(
     }
 }
 
+// 単語を登録し、学習履歴に追加を記録する
+async fn insert_word(
+    app_state: &AppState,
+    user_id: Uuid,
+    payload: &CreateWordRequest,
+    source: WordSource,
+    metadata: Option<WordSourceMetadata>,
+) -> Result<Word, (StatusCode, String)> {
+    // cuidの代わりにuuidを使用（文字列として）
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+
+    let sql = format!(
+        "INSERT INTO words (id, word, meaning, translation, part_of_speech, phonetic, example, category, synonyms, source, source_metadata, user_id, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+         RETURNING {}",
+        WORD_COLUMNS
+    );
+    let word = sqlx::query_as::<_, Word>(&sql)
+        .bind(&id)
+        .bind(&payload.word)
+        .bind(&payload.meaning)
+        .bind(&payload.translation)
+        .bind(serde_json::to_value(&payload.part_of_speech).unwrap())
+        .bind(&payload.phonetic)
+        .bind(&payload.example)
+        .bind(&payload.category)
+        .bind(serde_json::to_value(&payload.synonyms).unwrap())
+        .bind(source.as_str())
+        .bind(metadata.map(|metadata| serde_json::to_value(metadata).unwrap()))
+        .bind(user_id)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&app_state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    Activity::record(
+        &app_state.pool,
+        user_id,
+        ActivityKind::WordAdded,
+        Some(&word.id),
+        None,
+    )
+    .await
+    .map_err(internal_error)?;
+
+    Ok(word)
+}
+
+// 保存済みの会話分析から提案を取り出す
+async fn find_analysis_suggestion(
+    app_state: &AppState,
+    user_id: Uuid,
+    analysis_id: Uuid,
+    index: usize,
+) -> Result<WordSuggestion, (StatusCode, String)> {
+    let result = ConversationAnalysis::find_result(&app_state.pool, analysis_id, user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Analysis not found".to_string()))?;
+    let analysis: ConversationAnalysisResponse =
+        serde_json::from_value(result).map_err(internal_error)?;
+
+    analysis
+        .suggestions
+        .into_iter()
+        .nth(index)
+        .ok_or((StatusCode::NOT_FOUND, "Suggestion not found".to_string()))
+}
+
+/// "noun/verb" や "adjective, adverb" のような品詞の文字列を配列にする
+fn split_part_of_speech(value: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for part in value
+        .split(['/', ',', ';', '|'])
+        .flat_map(|part| part.split(" or "))
+    {
+        let part = part.trim().to_lowercase();
+        if !part.is_empty() && !parts.contains(&part) {
+            parts.push(part);
+        }
+    }
+    parts
+}
+
+async fn fetch_words(
+    app_state: &AppState,
+    user_id: Uuid,
+    statuses: Option<Vec<String>>,
+) -> Result<Vec<Word>, (StatusCode, String)> {
+    let sql = format!(
+        "SELECT {} FROM words
+         WHERE user_id = $1 AND ($2::text[] IS NULL OR status = ANY($2))
+         ORDER BY created_at DESC",
+        WORD_COLUMNS
+    );
+    sqlx::query_as::<_, Word>(&sql)
+        .bind(user_id)
+        .bind(statuses)
+        .fetch_all(&app_state.pool)
+        .await
+        .map_err(internal_error)
+}
+
+fn words_to_csv(words: &[Word]) -> String {
+    let mut csv = String::from(
+        "word,meaning,translation,part_of_speech,phonetic,example,category,synonyms,status,created_at\n",
+    );
+    for word in words {
+        let fields = [
+            word.word.clone(),
+            word.meaning.clone(),
+            word.translation.clone().unwrap_or_default(),
+            word.part_of_speech.join("; "),
+            word.phonetic.clone().unwrap_or_default(),
+            word.example.clone().unwrap_or_default(),
+            word.category.clone().unwrap_or_default(),
+            word.synonyms.join("; "),
+            word.status.clone(),
+            word.created_at.to_rfc3339(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 // 内部エラーを統一的に扱うためのヘルパー関数
 fn internal_error<E>(err: E) -> (StatusCode, String)
 where
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DEFAULT_CONNECT_TIMEOUT_SECONDS: u64 = 5;
+const DEFAULT_READ_TIMEOUT_SECONDS: u64 = 60;
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const DEFAULT_CACHE_TTL_HOURS: u64 = 24 * 7;
+
+/// AIを使う処理の種類。種類ごとにモデルや生成パラメータを変えられる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AiTask {
+    /// 会話の分析 (/api/conversation-analysis)
+    Analysis,
+    /// 語彙ヘルプ (/api/vocabulary-help)
+    Help,
+    /// 単語の提案 (/api/word-suggestions)
+    Suggestions,
+    /// 回答・作文の採点
+    Grading,
+    /// 例文や覚え方、単語カードの内容の生成
+    Generation,
+    /// チューターとの会話練習
+    Tutor,
+}
+
+impl AiTask {
+    pub const ALL: [AiTask; 6] = [
+        AiTask::Analysis,
+        AiTask::Help,
+        AiTask::Suggestions,
+        AiTask::Grading,
+        AiTask::Generation,
+        AiTask::Tutor,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiTask::Analysis => "analysis",
+            AiTask::Help => "help",
+            AiTask::Suggestions => "suggestions",
+            AiTask::Grading => "grading",
+            AiTask::Generation => "generation",
+            AiTask::Tutor => "tutor",
+        }
+    }
+
+    /// 設定キーの接尾辞 (例: AI_MODEL_ANALYSIS)
+    fn key_suffix(&self) -> String {
+        self.as_str().to_uppercase()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    Gemini,
+    OpenAiCompatible,
+    Mock,
+}
+
+impl ProviderKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "gemini" => Some(ProviderKind::Gemini),
+            "openai" => Some(ProviderKind::OpenAiCompatible),
+            "mock" => Some(ProviderKind::Mock),
+            _ => None,
+        }
+    }
+
+    fn default_model(&self) -> &'static str {
+        match self {
+            ProviderKind::Gemini => "gemini-2.5-flash-lite",
+            // OpenAI 互換のサーバーはモデル名がサーバーごとに違うので必ず指定してもらう
+            ProviderKind::OpenAiCompatible => "",
+            ProviderKind::Mock => "mock",
+        }
+    }
+
+    fn default_base_url(&self) -> &'static str {
+        match self {
+            ProviderKind::Gemini => "https://generativelanguage.googleapis.com/v1beta",
+            ProviderKind::OpenAiCompatible => "https://api.openai.com/v1",
+            ProviderKind::Mock => "",
+        }
+    }
+}
+
+/// 1回の生成で使うモデルとパラメータ
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationSettings {
+    pub model: String,
+    pub temperature: Option<f32>,
+    pub max_output_tokens: Option<u32>,
+}
+
+/// Gemini のセーフティ設定 (例: HARM_CATEGORY_HARASSMENT = BLOCK_ONLY_HIGH)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+/// ユーザーごとのトークン使用量の上限 (None なら無制限)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AiQuota {
+    pub daily_tokens: Option<u64>,
+    pub monthly_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AiConfig {
+    pub provider: ProviderKind,
+    pub api_key: Option<String>,
+    pub base_url: String,
+    pub default: GenerationSettings,
+    /// 処理ごとの上書き (指定のない項目は default を使う)
+    pub tasks: HashMap<AiTask, GenerationSettings>,
+    pub safety_settings: Vec<SafetySetting>,
+    /// 接続を確立するまでの待ち時間の上限
+    pub connect_timeout: Duration,
+    /// 応答の受信が止まってから諦めるまでの時間
+    pub read_timeout: Duration,
+    /// 429 や 5xx が返ったときに再試行する回数
+    pub max_retries: u32,
+    /// 同じ質問への応答をキャッシュしておく期間 (0 ならキャッシュしない)
+    pub cache_ttl: Duration,
+    pub quota: AiQuota,
+}
+
+impl AiConfig {
+    /// シークレットから設定を読み込む
+    ///
+    /// AI_MODEL / AI_TEMPERATURE / AI_MAX_OUTPUT_TOKENS が全体の既定値で、
+    /// AI_MODEL_ANALYSIS のように処理名を付けたキーでその処理だけ上書きできる。
+    pub fn from_lookup(get: impl Fn(&str) -> Option<String>) -> Result<AiConfig, String> {
+        let get = |key: &str| {
+            get(key)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let provider_name = get("AI_PROVIDER").unwrap_or_else(|| "gemini".to_string());
+        let provider = ProviderKind::parse(&provider_name)
+            .ok_or_else(|| format!("Unknown AI_PROVIDER: {}", provider_name))?;
+
+        let api_key = match provider {
+            ProviderKind::Gemini => get("GEMINI_API_KEY").or_else(|| get("AI_API_KEY")),
+            _ => get("AI_API_KEY"),
+        };
+
+        let default = GenerationSettings {
+            model: get("AI_MODEL").unwrap_or_else(|| provider.default_model().to_string()),
+            temperature: parse_temperature(get("AI_TEMPERATURE"), "AI_TEMPERATURE")?,
+            max_output_tokens: parse_max_tokens(
+                get("AI_MAX_OUTPUT_TOKENS"),
+                "AI_MAX_OUTPUT_TOKENS",
+            )?,
+        };
+
+        let mut tasks = HashMap::new();
+        for task in AiTask::ALL {
+            let suffix = task.key_suffix();
+            let temperature_key = format!("AI_TEMPERATURE_{}", suffix);
+            let max_tokens_key = format!("AI_MAX_OUTPUT_TOKENS_{}", suffix);
+            let settings = GenerationSettings {
+                model: get(&format!("AI_MODEL_{}", suffix)).unwrap_or(default.model.clone()),
+                temperature: parse_temperature(get(&temperature_key), &temperature_key)?
+                    .or(default.temperature),
+                max_output_tokens: parse_max_tokens(get(&max_tokens_key), &max_tokens_key)?
+                    .or(default.max_output_tokens),
+            };
+            if settings != default {
+                tasks.insert(task, settings);
+            }
+        }
+
+        Ok(AiConfig {
+            provider,
+            api_key,
+            base_url: get("AI_BASE_URL")
+                .unwrap_or_else(|| provider.default_base_url().to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            default,
+            tasks,
+            safety_settings: parse_safety_settings(get("AI_SAFETY_SETTINGS"))?,
+            connect_timeout: Duration::from_secs(
+                parse_number(
+                    get("AI_CONNECT_TIMEOUT_SECONDS"),
+                    "AI_CONNECT_TIMEOUT_SECONDS",
+                    1,
+                )?
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECONDS),
+            ),
+            read_timeout: Duration::from_secs(
+                parse_number(get("AI_TIMEOUT_SECONDS"), "AI_TIMEOUT_SECONDS", 1)?
+                    .unwrap_or(DEFAULT_READ_TIMEOUT_SECONDS),
+            ),
+            max_retries: parse_number(get("AI_MAX_RETRIES"), "AI_MAX_RETRIES", 0)?
+                .map(|n| n as u32)
+                .unwrap_or(DEFAULT_MAX_RETRIES),
+            cache_ttl: Duration::from_secs(
+                parse_number(get("AI_CACHE_TTL_HOURS"), "AI_CACHE_TTL_HOURS", 0)?
+                    .unwrap_or(DEFAULT_CACHE_TTL_HOURS)
+                    * 3600,
+            ),
+            quota: AiQuota {
+                // 0 は無制限として扱う
+                daily_tokens: parse_number(get("AI_DAILY_TOKEN_QUOTA"), "AI_DAILY_TOKEN_QUOTA", 0)?
+                    .filter(|&n| n > 0),
+                monthly_tokens: parse_number(
+                    get("AI_MONTHLY_TOKEN_QUOTA"),
+                    "AI_MONTHLY_TOKEN_QUOTA",
+                    0,
+                )?
+                .filter(|&n| n > 0),
+            },
+        })
+    }
+
+    pub fn settings_for(&self, task: AiTask) -> &GenerationSettings {
+        self.tasks.get(&task).unwrap_or(&self.default)
+    }
+}
+
+fn parse_temperature(value: Option<String>, key: &str) -> Result<Option<f32>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<f32>() {
+        Ok(t) if (0.0..=2.0).contains(&t) => Ok(Some(t)),
+        _ => Err(format!("{} must be a number between 0 and 2", key)),
+    }
+}
+
+fn parse_max_tokens(value: Option<String>, key: &str) -> Result<Option<u32>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(Some(n)),
+        _ => Err(format!("{} must be a positive integer", key)),
+    }
+}
+
+fn parse_number(value: Option<String>, key: &str, min: u64) -> Result<Option<u64>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<u64>() {
+        Ok(n) if n >= min && n <= u32::MAX as u64 => Ok(Some(n)),
+        _ => Err(format!("{} must be an integer of at least {}", key, min)),
+    }
+}
+
+/// "HARM_CATEGORY_HARASSMENT=BLOCK_ONLY_HIGH,HARM_CATEGORY_HATE_SPEECH=BLOCK_NONE" の形式
+fn parse_safety_settings(value: Option<String>) -> Result<Vec<SafetySetting>, String> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((category, threshold)) if !category.trim().is_empty() => Ok(SafetySetting {
+                category: category.trim().to_string(),
+                threshold: threshold.trim().to_string(),
+            }),
+            _ => Err(format!(
+                "AI_SAFETY_SETTINGS entries must look like CATEGORY=THRESHOLD: {}",
+                pair
+            )),
+        })
+        .collect()
+}
@@ -0,0 +1,276 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use super::{
+    request_error, sse, status_error, strip_code_fence, AiConfig, AiTask, Completion, LlmError,
+    LlmProvider, TokenUsage,
+};
+
+// Internal Gemini API structs
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GeminiGenerationConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    safety_settings: Vec<GeminiSafetySetting>,
+}
+
+#[derive(Serialize)]
+struct GeminiContent {
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Serialize)]
+struct GeminiPart {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_schema: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct GeminiSafetySetting {
+    category: String,
+    threshold: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    #[serde(default)]
+    usage_metadata: Option<GeminiUsageMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+}
+
+// ストリーミングの最後のチャンクには本文が無いことがあるので、欠けていても読めるようにする
+#[derive(Deserialize)]
+struct GeminiCandidate {
+    #[serde(default)]
+    content: GeminiResponseContent,
+}
+
+#[derive(Deserialize, Default)]
+struct GeminiResponseContent {
+    #[serde(default)]
+    parts: Vec<GeminiResponsePart>,
+}
+
+#[derive(Deserialize)]
+struct GeminiResponsePart {
+    #[serde(default)]
+    text: String,
+}
+
+impl GeminiResponse {
+    fn text(&self) -> Option<String> {
+        let parts = &self.candidates.first()?.content.parts;
+        if parts.is_empty() {
+            return None;
+        }
+        Some(parts.iter().map(|p| p.text.as_str()).collect())
+    }
+
+    fn usage(&self) -> Option<TokenUsage> {
+        self.usage_metadata.as_ref().map(|u| TokenUsage {
+            input_tokens: u.prompt_token_count,
+            output_tokens: u.candidates_token_count,
+        })
+    }
+}
+
+/// Google Gemini API
+pub struct GeminiProvider {
+    config: AiConfig,
+    client: reqwest::Client,
+}
+
+impl GeminiProvider {
+    pub fn new(config: AiConfig, client: reqwest::Client) -> Self {
+        Self { config, client }
+    }
+
+    /// リクエストを送り、成功のステータスが返ったレスポンスを返す
+    ///
+    /// `stream` なら streamGenerateContent を SSE 形式で呼ぶ。
+    async fn send(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        response_schema: Option<&Value>,
+        stream: bool,
+    ) -> Result<reqwest::Response, LlmError> {
+        let Some(api_key) = &self.config.api_key else {
+            return Err(LlmError::NotConfigured(
+                "Gemini API key is not configured".to_string(),
+            ));
+        };
+        let settings = self.config.settings_for(task);
+
+        let url = if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse&key={}",
+                self.config.base_url, settings.model, api_key
+            )
+        } else {
+            format!(
+                "{}/models/{}:generateContent?key={}",
+                self.config.base_url, settings.model, api_key
+            )
+        };
+
+        let generation_config = if settings.temperature.is_some()
+            || settings.max_output_tokens.is_some()
+            || response_schema.is_some()
+        {
+            Some(GeminiGenerationConfig {
+                temperature: settings.temperature,
+                max_output_tokens: settings.max_output_tokens,
+                response_mime_type: response_schema.map(|_| "application/json"),
+                response_schema: response_schema.cloned(),
+            })
+        } else {
+            None
+        };
+        let request = GeminiRequest {
+            contents: vec![GeminiContent {
+                parts: vec![GeminiPart {
+                    text: prompt.to_string(),
+                }],
+            }],
+            generation_config,
+            safety_settings: self
+                .config
+                .safety_settings
+                .iter()
+                .map(|s| GeminiSafetySetting {
+                    category: s.category.clone(),
+                    threshold: s.threshold.clone(),
+                })
+                .collect(),
+        };
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        if !response.status().is_success() {
+            return Err(status_error(&response));
+        }
+        Ok(response)
+    }
+
+    async fn request(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        response_schema: Option<&Value>,
+    ) -> Result<Completion, LlmError> {
+        let response = self.send(task, prompt, response_schema, false).await?;
+
+        let gemini_response: GeminiResponse = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                LlmError::Timeout
+            } else {
+                LlmError::InvalidResponse(e.to_string())
+            }
+        })?;
+
+        let raw_text = gemini_response.text().ok_or(LlmError::InvalidResponse(
+            "No response from Gemini".to_string(),
+        ))?;
+
+        Ok(Completion {
+            text: strip_code_fence(&raw_text),
+            usage: gemini_response.usage().unwrap_or_default(),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn model(&self, task: AiTask) -> String {
+        self.config.settings_for(task).model.clone()
+    }
+
+    async fn generate(&self, task: AiTask, prompt: &str) -> Result<Completion, LlmError> {
+        self.request(task, prompt, None).await
+    }
+
+    async fn generate_json(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+    ) -> Result<Completion, LlmError> {
+        self.request(task, prompt, Some(schema)).await
+    }
+
+    async fn generate_stream(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<Completion, LlmError> {
+        let response = self.send(task, prompt, Some(schema), true).await?;
+
+        let mut text = String::new();
+        let mut usage = TokenUsage::default();
+        sse::for_each_data(response, |data| {
+            let chunk: GeminiResponse =
+                serde_json::from_str(data).map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+            // 使用量は累計で届くので最後の値を使う
+            if let Some(chunk_usage) = chunk.usage() {
+                usage = chunk_usage;
+            }
+            if let Some(delta) = chunk.text().filter(|t| !t.is_empty()) {
+                text.push_str(&delta);
+                let _ = deltas.send(delta);
+            }
+            Ok(())
+        })
+        .await?;
+
+        if text.is_empty() {
+            return Err(LlmError::InvalidResponse(
+                "No response from Gemini".to_string(),
+            ));
+        }
+        Ok(Completion {
+            text: strip_code_fence(&text),
+            usage,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+
+use super::prompt::USER_INPUT_TAG;
+use super::structured::matching_brace;
+use super::{AiTask, Completion, LlmError, LlmProvider, TokenUsage};
+
+/// ネットワークを使わない決定的なプロバイダ (オフラインでの開発・テスト用)
+///
+/// プロンプトには応答形式の JSON 例が含まれているので、その例をそのまま応答として返す。
+/// 利用者の入力に含まれる JSON は例として扱わない。同じプロンプトには常に同じ応答を返す。
+pub struct MockProvider;
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// プロンプト中で最初に現れる、JSON として読めるオブジェクト (利用者の入力の中は探さない)
+fn example_json(prompt: &str) -> Option<String> {
+    let prompt = without_user_input(prompt);
+    let prompt = prompt.as_str();
+    prompt
+        .char_indices()
+        .filter(|&(_, c)| c == '{')
+        .find_map(|(start, _)| {
+            let end = matching_brace(prompt, start)?;
+            let candidate = &prompt[start..=end];
+            match serde_json::from_str::<serde_json::Value>(candidate) {
+                Ok(value) if value.as_object().is_some_and(|o| !o.is_empty()) => {
+                    Some(candidate.to_string())
+                }
+                _ => None,
+            }
+        })
+}
+
+/// `<user_input ...>` から `</user_input>` までを取り除く
+fn without_user_input(prompt: &str) -> String {
+    let open = format!("<{}", USER_INPUT_TAG);
+    let close = format!("</{}>", USER_INPUT_TAG);
+    let mut result = String::with_capacity(prompt.len());
+    let mut rest = prompt;
+    while let Some(start) = rest.find(&open) {
+        result.push_str(&rest[..start]);
+        rest = match rest[start..].find(&close) {
+            Some(end) => &rest[start + end + close.len()..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn model(&self, _task: AiTask) -> String {
+        "mock".to_string()
+    }
+
+    async fn generate(&self, _task: AiTask, prompt: &str) -> Result<Completion, LlmError> {
+        let text = example_json(prompt).ok_or(LlmError::InvalidResponse(
+            "Mock provider found no JSON example in the prompt".to_string(),
+        ))?;
+        // 使用量の集計を試せるよう概算する
+        let usage = TokenUsage::estimate(prompt, &text);
+        Ok(Completion { text, usage })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::prompt::{self, PromptVar};
+
+    #[tokio::test]
+    async fn returns_the_template_example_not_json_from_user_input() {
+        let prompt = prompt::VOCABULARY_HELP.render(&[
+            PromptVar::text("level", "B1"),
+            PromptVar::text("stretch_level", "B2"),
+            PromptVar::user_input("context", r#"{"explanation": "from the context"}"#),
+            PromptVar::user_input("question", r#"What does {"a": 1} mean?"#),
+        ]);
+
+        let completion = MockProvider::new()
+            .generate(AiTask::Help, &prompt.text)
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&completion.text).unwrap();
+        assert_eq!(
+            value["explanation"],
+            "clear explanation answering the user's question"
+        );
+        assert!(completion.usage.input_tokens > 0);
+    }
+
+    #[tokio::test]
+    async fn fails_when_only_user_input_has_json() {
+        let prompt = r#"Answer the question.
+<user_input name="question">{"word": "run"}</user_input>"#;
+
+        let result = MockProvider::new().generate(AiTask::Help, prompt).await;
+        assert!(matches!(result, Err(LlmError::InvalidResponse(_))));
+    }
+
+    #[test]
+    fn strips_user_input_regions() {
+        assert_eq!(
+            without_user_input(r#"a <user_input name="x">{"b": 1}</user_input> c"#),
+            "a  c"
+        );
+        // 閉じタグが無ければ末尾まで利用者の入力とみなす
+        assert_eq!(
+            without_user_input(r#"a <user_input name="x">{"b": 1}"#),
+            "a "
+        );
+        assert_eq!(without_user_input("no input"), "no input");
+    }
+}
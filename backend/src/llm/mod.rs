@@ -0,0 +1,404 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use shuttle_axum::axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tokio::sync::mpsc;
+
+pub mod config;
+pub mod gemini;
+pub mod mock;
+pub mod openai;
+pub mod prompt;
+pub mod resilience;
+pub mod sse;
+pub mod structured;
+
+pub use config::{AiConfig, AiQuota, AiTask, ProviderKind};
+pub use gemini::GeminiProvider;
+pub use mock::MockProvider;
+pub use openai::OpenAiCompatibleProvider;
+pub use resilience::ResilientProvider;
+pub use structured::{generate_structured, StructuredOutput};
+
+/// 一時的な障害のとき、クライアントに再試行を促すまでの秒数の既定値
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// LLM 呼び出しのエラー
+#[derive(Debug)]
+pub enum LlmError {
+    /// API キーなどが設定されていない
+    NotConfigured(String),
+    /// 接続できない・応答が途中で切れた
+    Request(String),
+    /// 接続または応答の待ち時間が上限を超えた
+    Timeout,
+    /// プロバイダが成功以外のステータスを返した (Retry-After があればその値)
+    Status {
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    /// 障害が続いているので呼び出しを止めている
+    CircuitOpen { retry_after: Duration },
+    /// 応答の形式が想定と違う
+    InvalidResponse(String),
+}
+
+impl LlmError {
+    /// 時間をおいて再試行すれば成功しうるエラーか
+    pub fn is_transient(&self) -> bool {
+        match self {
+            LlmError::Request(_) => true,
+            LlmError::Status { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for LlmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlmError::NotConfigured(message) => write!(f, "{}", message),
+            LlmError::Request(message) => write!(f, "Request failed: {}", message),
+            LlmError::Timeout => write!(f, "AI provider did not respond in time"),
+            LlmError::Status { status, .. } => write!(f, "AI provider error: {}", status),
+            LlmError::CircuitOpen { .. } => write!(f, "AI provider is temporarily unavailable"),
+            LlmError::InvalidResponse(message) => write!(f, "Invalid AI response: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+/// クライアントに返す AI 機能のエラー
+///
+/// `{"error": "ai_invalid_output", "message": "..."}` の形の JSON で返し、
+/// 一時的な障害には Retry-After ヘッダーを付ける。
+#[derive(Debug)]
+pub enum AiError {
+    /// AI が設定されていない
+    NotConfigured(String),
+    /// レート制限や障害で一時的に使えない
+    Unavailable {
+        message: String,
+        retry_after: Duration,
+    },
+    /// プロバイダに接続できない・エラーが返った
+    Provider(String),
+    /// プロバイダの応答が時間内に返らなかった
+    Timeout(String),
+    /// 作り直させても使える出力が得られなかった
+    InvalidOutput {
+        reason: String,
+        /// 失敗までに使ったトークン (まだ記録していない分)
+        usage: TokenUsage,
+    },
+    /// ユーザーの使用量が上限に達した
+    QuotaExceeded {
+        period: &'static str,
+        limit: u64,
+        used: u64,
+        resets_at: DateTime<Utc>,
+    },
+    /// リクエストの内容が正しくない
+    BadRequest(String),
+    /// 対象 (単語など) が見つからない
+    NotFound(String),
+    /// 処理中に対象が他のリクエストで更新された
+    Conflict(String),
+    /// 使用量の記録などサーバー側の処理に失敗した
+    Internal(String),
+}
+
+impl AiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AiError::NotConfigured(_) | AiError::Unavailable { .. } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            AiError::Provider(_) | AiError::InvalidOutput { .. } => StatusCode::BAD_GATEWAY,
+            AiError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AiError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AiError::NotFound(_) => StatusCode::NOT_FOUND,
+            AiError::Conflict(_) => StatusCode::CONFLICT,
+            AiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AiError::NotConfigured(_) => "ai_not_configured",
+            AiError::Unavailable { .. } => "ai_unavailable",
+            AiError::Provider(_) => "ai_provider_error",
+            AiError::Timeout(_) => "ai_timeout",
+            AiError::InvalidOutput { .. } => "ai_invalid_output",
+            AiError::QuotaExceeded { .. } => "ai_quota_exceeded",
+            AiError::BadRequest(_) => "invalid_request",
+            AiError::NotFound(_) => "not_found",
+            AiError::Conflict(_) => "conflict",
+            AiError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Retry-After に入れる待ち時間 (設定の不備など、待っても直らないものは None)
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AiError::Unavailable { retry_after, .. } => Some(*retry_after),
+            AiError::Provider(_) | AiError::Timeout(_) => Some(DEFAULT_RETRY_AFTER),
+            AiError::QuotaExceeded { resets_at, .. } => {
+                Some((*resets_at - Utc::now()).to_std().unwrap_or_default())
+            }
+            AiError::NotConfigured(_)
+            | AiError::InvalidOutput { .. }
+            | AiError::BadRequest(_)
+            | AiError::NotFound(_)
+            | AiError::Conflict(_)
+            | AiError::Internal(_) => None,
+        }
+    }
+
+    /// 失敗するまでに使ったトークン (使えない出力を生成した分など)
+    pub fn usage(&self) -> TokenUsage {
+        match self {
+            AiError::InvalidOutput { usage, .. } => *usage,
+            _ => TokenUsage::default(),
+        }
+    }
+}
+
+impl std::fmt::Display for AiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AiError::NotConfigured(message)
+            | AiError::Unavailable { message, .. }
+            | AiError::Provider(message)
+            | AiError::Timeout(message)
+            | AiError::BadRequest(message)
+            | AiError::NotFound(message)
+            | AiError::Conflict(message)
+            | AiError::Internal(message) => write!(f, "{}", message),
+            AiError::QuotaExceeded {
+                period,
+                limit,
+                resets_at,
+                ..
+            } => write!(
+                f,
+                "You have used your {} AI quota of {} tokens. It resets at {}",
+                period,
+                limit,
+                resets_at.to_rfc3339()
+            ),
+            AiError::InvalidOutput { reason, .. } => {
+                write!(
+                    f,
+                    "The AI returned a response in an unexpected format: {}",
+                    reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AiError {}
+
+impl From<LlmError> for AiError {
+    fn from(err: LlmError) -> Self {
+        let message = err.to_string();
+        match err {
+            LlmError::NotConfigured(message) => AiError::NotConfigured(message),
+            LlmError::Timeout => AiError::Timeout(message),
+            LlmError::CircuitOpen { retry_after } => AiError::Unavailable {
+                message,
+                retry_after,
+            },
+            // レート制限・過負荷は時間をおけば回復するので 503 にする
+            LlmError::Status {
+                status: 429 | 503,
+                retry_after,
+            } => AiError::Unavailable {
+                message,
+                retry_after: retry_after.unwrap_or(DEFAULT_RETRY_AFTER),
+            },
+            _ => AiError::Provider(message),
+        }
+    }
+}
+
+impl IntoResponse for AiError {
+    fn into_response(self) -> Response {
+        let mut body = json!({ "error": self.code(), "message": self.to_string() });
+        if let AiError::QuotaExceeded {
+            period,
+            limit,
+            used,
+            resets_at,
+        } = &self
+        {
+            body["period"] = json!(period);
+            body["limit"] = json!(limit);
+            body["used"] = json!(used);
+            body["resets_at"] = json!(resets_at);
+        }
+        let mut response = (self.status(), Json(body)).into_response();
+        if let Some(retry_after) = self.retry_after() {
+            // 秒数は切り上げる (0 秒だとすぐに再試行されてしまう)
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+        }
+        response
+    }
+}
+
+// AI 以外のエラーも返すハンドラ向け (ステータスは AI のエラーに合わせる)
+impl From<AiError> for (StatusCode, String) {
+    fn from(err: AiError) -> Self {
+        (err.status(), err.to_string())
+    }
+}
+
+/// プロバイダが報告したトークン使用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+impl TokenUsage {
+    /// 4文字を1トークンとして概算する (プロバイダの報告が無いとき用)
+    pub fn estimate(prompt: &str, output: &str) -> TokenUsage {
+        TokenUsage {
+            input_tokens: prompt.chars().count().div_ceil(4) as u32,
+            output_tokens: output.chars().count().div_ceil(4) as u32,
+        }
+    }
+}
+
+impl std::ops::Add for TokenUsage {
+    type Output = TokenUsage;
+
+    fn add(self, other: TokenUsage) -> TokenUsage {
+        TokenUsage {
+            input_tokens: self.input_tokens + other.input_tokens,
+            output_tokens: self.output_tokens + other.output_tokens,
+        }
+    }
+}
+
+/// 生成結果
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+/// テキスト生成を行う LLM のプロバイダ
+///
+/// ハンドラはこのトレイト越しに呼び出すので、プロバイダを差し替えても変更は要らない。
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// ログやレスポンスに出すプロバイダ名
+    fn name(&self) -> &'static str;
+
+    /// `task` で使うモデル名 (キャッシュのキーに使う)
+    fn model(&self, task: AiTask) -> String;
+
+    /// プロンプトに対する応答テキストと使用量を返す (コードブロックの囲みは取り除く)
+    ///
+    /// モデルや生成パラメータは `task` ごとの設定を使う。
+    async fn generate(&self, task: AiTask, prompt: &str) -> Result<Completion, LlmError>;
+
+    /// 応答スキーマを指定して JSON を生成する
+    ///
+    /// スキーマに対応していないプロバイダはプロンプト中の形式指定だけに頼る。
+    async fn generate_json(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        _schema: &Value,
+    ) -> Result<Completion, LlmError> {
+        self.generate(task, prompt).await
+    }
+    /// `generate_json` のストリーミング版。届いたテキストを順に `deltas` へ送り、最後に全体を返す
+    ///
+    /// ストリーミングに対応していないプロバイダは、生成し終えた全体を1回で送る。
+    /// 受け手が閉じていても生成は続けるので、中断したい場合は返り値の Future を破棄する。
+    async fn generate_stream(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<Completion, LlmError> {
+        let completion = self.generate_json(task, prompt, schema).await?;
+        let _ = deltas.send(completion.text.clone());
+        Ok(completion)
+    }
+}
+
+/// AI の呼び出しに使う HTTP クライアント (接続はプールして使い回す)
+pub fn build_http_client(config: &AiConfig) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .read_timeout(config.read_timeout)
+        .build()
+}
+
+/// 設定に応じたプロバイダを作る (外部 API を呼ぶものには再試行とサーキットブレーカーを付ける)
+pub fn build_provider(config: AiConfig, client: reqwest::Client) -> Arc<dyn LlmProvider> {
+    let max_retries = config.max_retries;
+    let inner: Box<dyn LlmProvider> = match config.provider {
+        ProviderKind::Gemini => Box::new(GeminiProvider::new(config, client)),
+        ProviderKind::OpenAiCompatible => Box::new(OpenAiCompatibleProvider::new(config, client)),
+        ProviderKind::Mock => return Arc::new(MockProvider::new()),
+    };
+    Arc::new(ResilientProvider::new(inner, max_retries))
+}
+
+/// 接続・送受信のエラーをタイムアウトとそれ以外に分ける
+pub(crate) fn request_error(err: reqwest::Error) -> LlmError {
+    if err.is_timeout() {
+        LlmError::Timeout
+    } else {
+        LlmError::Request(err.to_string())
+    }
+}
+
+/// 失敗したレスポンスをステータスと Retry-After (秒数) のエラーにする
+pub(crate) fn status_error(response: &reqwest::Response) -> LlmError {
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    LlmError::Status {
+        status: response.status().as_u16(),
+        retry_after,
+    }
+}
+
+/// 応答全体が ``` で囲まれていれば中身だけを取り出す
+pub(crate) fn strip_code_fence(raw_text: &str) -> String {
+    let trimmed = raw_text.trim();
+    match trimmed
+        .strip_prefix("```")
+        .and_then(|s| s.strip_suffix("```"))
+    {
+        Some(inner) => inner
+            .strip_prefix("json")
+            .unwrap_or(inner)
+            .trim()
+            .to_string(),
+        None => trimmed.to_string(),
+    }
+}
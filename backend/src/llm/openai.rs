@@ -0,0 +1,270 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use super::{
+    request_error, sse, status_error, strip_code_fence, AiConfig, AiTask, Completion, LlmError,
+    LlmProvider, TokenUsage,
+};
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatStreamChunk {
+    #[serde(default)]
+    choices: Vec<ChatStreamChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatStreamChoice {
+    #[serde(default)]
+    delta: ChatResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Deserialize, Default)]
+struct ChatResponseMessage {
+    content: Option<String>,
+}
+
+/// OpenAI 互換の Chat Completions API
+///
+/// OpenAI のほか、llama.cpp の server や Ollama (`http://localhost:11434/v1`) も使える。
+pub struct OpenAiCompatibleProvider {
+    config: AiConfig,
+    client: reqwest::Client,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(config: AiConfig, client: reqwest::Client) -> Self {
+        Self { config, client }
+    }
+
+    /// リクエストを送り、成功のステータスが返ったレスポンスを返す
+    async fn send(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        response_format: Option<Value>,
+        stream: bool,
+    ) -> Result<reqwest::Response, LlmError> {
+        let settings = self.config.settings_for(task);
+        if self.config.base_url.is_empty() || settings.model.is_empty() {
+            return Err(LlmError::NotConfigured(
+                "AI base URL or model is not configured".to_string(),
+            ));
+        }
+
+        let request = ChatRequest {
+            model: &settings.model,
+            messages: vec![ChatMessage {
+                role: "user",
+                content: prompt,
+            }],
+            temperature: settings.temperature,
+            max_tokens: settings.max_output_tokens,
+            response_format,
+            stream,
+            // 最後のチャンクで使用量を返してもらう
+            stream_options: stream.then(|| json!({ "include_usage": true })),
+        };
+
+        let mut builder = self
+            .client
+            .post(format!("{}/chat/completions", self.config.base_url))
+            .json(&request);
+        // ローカルサーバーでは API キーが不要なことが多い
+        if let Some(api_key) = &self.config.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = builder.send().await.map_err(request_error)?;
+
+        if !response.status().is_success() {
+            return Err(status_error(&response));
+        }
+        Ok(response)
+    }
+
+    async fn request(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        response_format: Option<Value>,
+    ) -> Result<Completion, LlmError> {
+        let response = self.send(task, prompt, response_format, false).await?;
+
+        let chat_response: ChatResponse = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                LlmError::Timeout
+            } else {
+                LlmError::InvalidResponse(e.to_string())
+            }
+        })?;
+
+        let raw_text = chat_response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .ok_or(LlmError::InvalidResponse(
+                "No choices in AI response".to_string(),
+            ))?;
+
+        let usage = chat_response
+            .usage
+            .map(|u| TokenUsage {
+                input_tokens: u.prompt_tokens,
+                output_tokens: u.completion_tokens,
+            })
+            .unwrap_or_default();
+
+        Ok(Completion {
+            text: strip_code_fence(&raw_text),
+            usage,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &'static str {
+        "openai_compatible"
+    }
+
+    fn model(&self, task: AiTask) -> String {
+        self.config.settings_for(task).model.clone()
+    }
+
+    async fn generate(&self, task: AiTask, prompt: &str) -> Result<Completion, LlmError> {
+        self.request(task, prompt, None).await
+    }
+
+    async fn generate_json(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+    ) -> Result<Completion, LlmError> {
+        self.request(task, prompt, Some(json_schema_format(schema)))
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<Completion, LlmError> {
+        let response = self
+            .send(task, prompt, Some(json_schema_format(schema)), true)
+            .await?;
+
+        let mut text = String::new();
+        let mut usage = TokenUsage::default();
+        sse::for_each_data(response, |data| {
+            if data.trim() == "[DONE]" {
+                return Ok(());
+            }
+            let chunk: ChatStreamChunk =
+                serde_json::from_str(data).map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+            if let Some(u) = chunk.usage {
+                usage = TokenUsage {
+                    input_tokens: u.prompt_tokens,
+                    output_tokens: u.completion_tokens,
+                };
+            }
+            for choice in chunk.choices {
+                if let Some(delta) = choice.delta.content.filter(|t| !t.is_empty()) {
+                    text.push_str(&delta);
+                    let _ = deltas.send(delta);
+                }
+            }
+            Ok(())
+        })
+        .await?;
+
+        if text.is_empty() {
+            return Err(LlmError::InvalidResponse(
+                "No choices in AI response".to_string(),
+            ));
+        }
+        Ok(Completion {
+            text: strip_code_fence(&text),
+            usage,
+        })
+    }
+}
+
+fn json_schema_format(schema: &Value) -> Value {
+    json!({
+        "type": "json_schema",
+        "json_schema": { "name": "response", "schema": to_json_schema(schema) },
+    })
+}
+
+/// Gemini 形式のスキーマを JSON Schema に直す
+///
+/// `nullable: true` は `"null"` 型の追加に置き換え、Gemini 専用の `propertyOrdering` は取り除く。
+fn to_json_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => {
+            let mut converted: serde_json::Map<String, Value> = map
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "nullable" | "propertyOrdering"))
+                .map(|(key, value)| (key.clone(), to_json_schema(value)))
+                .collect();
+            if map.get("nullable") == Some(&Value::Bool(true)) {
+                if let Some(kind) = map.get("type").cloned() {
+                    converted.insert("type".to_string(), json!([kind, "null"]));
+                }
+            }
+            Value::Object(converted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(to_json_schema).collect()),
+        other => other.clone(),
+    }
+}
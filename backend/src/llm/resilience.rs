@@ -0,0 +1,386 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rand::Rng;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use super::{AiTask, Completion, LlmError, LlmProvider};
+
+/// 最初の再試行までの待ち時間 (以降は倍々に延ばす)
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// これより長い Retry-After が返ったら再試行せずにエラーを返す
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// 連続でこの回数失敗したら呼び出しを止める
+const FAILURE_THRESHOLD: u32 = 5;
+/// 呼び出しを止めておく時間
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+/// 試しの呼び出しが終わるまで、他のリクエストに返す待ち時間
+const PROBE_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+/// プロバイダの障害時に呼び出しを止めるサーキットブレーカー
+///
+/// 一時的なエラーが `FAILURE_THRESHOLD` 回続くと `OPEN_DURATION` の間は呼び出しを止め、
+/// その後に1件だけ試しに通す。成功すれば元に戻り、失敗すればまた止める。
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    state: Mutex<BreakerState>,
+}
+
+/// 呼び出しの許可
+///
+/// 試しの呼び出しの許可が結果を記録されずに破棄されたら (クライアントの切断で Future が
+/// 捨てられた、設定の不備で失敗したなど)、次の試しを通せるよう印を外す。
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl Permit<'_> {
+    fn success(mut self) {
+        self.probe = false;
+        self.breaker.record_success();
+    }
+
+    fn failure(mut self) {
+        self.probe = false;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            self.breaker.state.lock().unwrap().probing = false;
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// 呼び出してよいか。止めている間は再開までの時間を返す
+    fn acquire(&self) -> Result<Permit<'_>, Duration> {
+        let mut state = self.state.lock().unwrap();
+        let Some(open_until) = state.open_until else {
+            return Ok(Permit {
+                breaker: self,
+                probe: false,
+            });
+        };
+        let now = Instant::now();
+        if now < open_until {
+            return Err(open_until - now);
+        }
+        if state.probing {
+            return Err(PROBE_RETRY_AFTER);
+        }
+        state.probing = true;
+        Ok(Permit {
+            breaker: self,
+            probe: true,
+        })
+    }
+
+    fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::default();
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.probing || state.consecutive_failures >= FAILURE_THRESHOLD {
+            if state.open_until.is_none() || state.probing {
+                println!(
+                    "AI provider failed {} times in a row, pausing calls for {}s",
+                    state.consecutive_failures,
+                    OPEN_DURATION.as_secs()
+                );
+            }
+            state.open_until = Some(Instant::now() + OPEN_DURATION);
+        }
+        state.probing = false;
+    }
+}
+
+/// 再試行とサーキットブレーカーを付けたプロバイダ
+pub struct ResilientProvider {
+    inner: Box<dyn LlmProvider>,
+    max_retries: u32,
+    breaker: CircuitBreaker,
+}
+
+impl ResilientProvider {
+    pub fn new(inner: Box<dyn LlmProvider>, max_retries: u32) -> Self {
+        Self {
+            inner,
+            max_retries,
+            breaker: CircuitBreaker::default(),
+        }
+    }
+
+    /// `retryable` が true を返すエラーのときだけ再試行する
+    async fn call<F, Fut>(
+        &self,
+        retryable: fn(&LlmError) -> bool,
+        mut attempt: F,
+    ) -> Result<Completion, LlmError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Completion, LlmError>>,
+    {
+        let permit = self
+            .breaker
+            .acquire()
+            .map_err(|retry_after| LlmError::CircuitOpen { retry_after })?;
+
+        let mut retries = 0;
+        let result = loop {
+            let result = attempt().await;
+            let err = match result {
+                Err(ref err) if retryable(err) && retries < self.max_retries => err,
+                _ => break result,
+            };
+            let Some(delay) = retry_delay(err, retries) else {
+                break result;
+            };
+            retries += 1;
+            println!(
+                "{}; retrying AI request in {}ms ({}/{})",
+                err,
+                delay.as_millis(),
+                retries,
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        };
+
+        match &result {
+            Err(err) if err.is_transient() || matches!(err, LlmError::Timeout) => permit.failure(),
+            // 設定の不備はプロバイダの障害ではないので数えない (試しの呼び出しなら許可を返す)
+            Err(LlmError::NotConfigured(_)) => drop(permit),
+            _ => permit.success(),
+        }
+        result
+    }
+}
+
+/// 指数バックオフ (ゆらぎ付き) の待ち時間。Retry-After が長すぎる場合は None
+fn retry_delay(err: &LlmError, retries: u32) -> Option<Duration> {
+    let backoff = BASE_RETRY_DELAY * 2u32.pow(retries);
+    let jitter = rand::thread_rng().gen_range(0..=backoff.as_millis() as u64 / 2);
+    let delay = backoff + Duration::from_millis(jitter);
+    match err {
+        LlmError::Status {
+            retry_after: Some(retry_after),
+            ..
+        } if *retry_after > MAX_RETRY_DELAY => None,
+        LlmError::Status {
+            retry_after: Some(retry_after),
+            ..
+        } => Some(delay.max(*retry_after)),
+        _ => Some(delay),
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ResilientProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn model(&self, task: AiTask) -> String {
+        self.inner.model(task)
+    }
+
+    async fn generate(&self, task: AiTask, prompt: &str) -> Result<Completion, LlmError> {
+        self.call(LlmError::is_transient, || self.inner.generate(task, prompt))
+            .await
+    }
+
+    async fn generate_json(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+    ) -> Result<Completion, LlmError> {
+        self.call(LlmError::is_transient, || {
+            self.inner.generate_json(task, prompt, schema)
+        })
+        .await
+    }
+
+    async fn generate_stream(
+        &self,
+        task: AiTask,
+        prompt: &str,
+        schema: &Value,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<Completion, LlmError> {
+        // 途中まで送った後にやり直すと本文が重複するので、送り始める前のステータスエラーだけ再試行する
+        let before_streaming = |err: &LlmError| -> bool {
+            matches!(err, LlmError::Status { .. }) && err.is_transient()
+        };
+        self.call(before_streaming, || {
+            self.inner
+                .generate_stream(task, prompt, schema, deltas.clone())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockProvider;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    const PROMPT: &str = "Respond with {\"ok\": true}";
+
+    /// 決めておいたエラーを順に返し、尽きたらモックの応答を返すプロバイダ
+    struct ScriptedProvider {
+        errors: Mutex<VecDeque<LlmError>>,
+        calls: Arc<AtomicU32>,
+        hang: bool,
+    }
+
+    impl ScriptedProvider {
+        fn new(errors: Vec<LlmError>) -> Self {
+            Self {
+                errors: Mutex::new(errors.into()),
+                calls: Arc::new(AtomicU32::new(0)),
+                hang: false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn model(&self, _task: AiTask) -> String {
+            "scripted".to_string()
+        }
+
+        async fn generate(&self, task: AiTask, prompt: &str) -> Result<Completion, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.hang {
+                std::future::pending::<()>().await;
+            }
+            let error = self.errors.lock().unwrap().pop_front();
+            match error {
+                Some(err) => Err(err),
+                None => MockProvider::new().generate(task, prompt).await,
+            }
+        }
+    }
+
+    fn unavailable() -> LlmError {
+        LlmError::Status {
+            status: 503,
+            retry_after: None,
+        }
+    }
+
+    // 止めていた時間が過ぎ、次の呼び出しが試しになる状態にする
+    fn expire(provider: &ResilientProvider) {
+        let mut state = provider.breaker.state.lock().unwrap();
+        state.consecutive_failures = FAILURE_THRESHOLD;
+        state.open_until = Some(Instant::now() - Duration::from_secs(1));
+    }
+
+    fn probing(provider: &ResilientProvider) -> bool {
+        provider.breaker.state.lock().unwrap().probing
+    }
+
+    #[tokio::test]
+    async fn opens_after_consecutive_transient_failures() {
+        let inner = ScriptedProvider::new((0..FAILURE_THRESHOLD).map(|_| unavailable()).collect());
+        let calls = inner.calls.clone();
+        let provider = ResilientProvider::new(Box::new(inner), 0);
+
+        for _ in 0..FAILURE_THRESHOLD {
+            let result = provider.generate(AiTask::Help, PROMPT).await;
+            assert!(matches!(result, Err(LlmError::Status { status: 503, .. })));
+        }
+        let result = provider.generate(AiTask::Help, PROMPT).await;
+        assert!(matches!(result, Err(LlmError::CircuitOpen { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), FAILURE_THRESHOLD);
+    }
+
+    #[tokio::test]
+    async fn closes_after_a_successful_probe() {
+        let provider = ResilientProvider::new(Box::new(ScriptedProvider::new(Vec::new())), 0);
+        expire(&provider);
+
+        let completion = provider.generate(AiTask::Help, PROMPT).await.unwrap();
+        assert_eq!(completion.text, "{\"ok\": true}");
+        let state = provider.breaker.state.lock().unwrap();
+        assert_eq!(state.open_until, None);
+        assert_eq!(state.consecutive_failures, 0);
+        assert!(!state.probing);
+    }
+
+    #[tokio::test]
+    async fn reopens_when_the_probe_fails() {
+        let provider =
+            ResilientProvider::new(Box::new(ScriptedProvider::new(vec![unavailable()])), 0);
+        expire(&provider);
+
+        assert!(provider.generate(AiTask::Help, PROMPT).await.is_err());
+        assert!(!probing(&provider));
+        let result = provider.generate(AiTask::Help, PROMPT).await;
+        assert!(
+            matches!(result, Err(LlmError::CircuitOpen { retry_after }) if retry_after > PROBE_RETRY_AFTER)
+        );
+    }
+
+    #[test]
+    fn lets_only_one_probe_through_at_a_time() {
+        let breaker = CircuitBreaker::default();
+        breaker.state.lock().unwrap().open_until = Some(Instant::now() - Duration::from_secs(1));
+
+        let probe = breaker.acquire().unwrap();
+        assert!(matches!(breaker.acquire(), Err(retry_after) if retry_after == PROBE_RETRY_AFTER));
+        drop(probe);
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn releases_the_probe_when_the_call_is_dropped() {
+        let mut inner = ScriptedProvider::new(Vec::new());
+        inner.hang = true;
+        let provider = ResilientProvider::new(Box::new(inner), 0);
+        expire(&provider);
+
+        // クライアントが切断して Future が捨てられた場合
+        let call = provider.generate(AiTask::Help, PROMPT);
+        assert!(tokio::time::timeout(Duration::from_millis(10), call)
+            .await
+            .is_err());
+        assert!(!probing(&provider));
+    }
+
+    #[tokio::test]
+    async fn releases_the_probe_when_the_provider_is_not_configured() {
+        let inner = ScriptedProvider::new(vec![LlmError::NotConfigured("no key".to_string())]);
+        let provider = ResilientProvider::new(Box::new(inner), 0);
+        expire(&provider);
+
+        let result = provider.generate(AiTask::Help, PROMPT).await;
+        assert!(matches!(result, Err(LlmError::NotConfigured(_))));
+        assert!(!probing(&provider));
+        assert!(provider.generate(AiTask::Help, PROMPT).await.is_ok());
+    }
+}
@@ -0,0 +1,120 @@
+use super::{request_error, LlmError};
+
+/// プロバイダのストリーミング応答 (Server-Sent Events) を読み、`data:` 行ごとに `on_data` を呼ぶ
+///
+/// 行の途中や UTF-8 の途中で切れたチャンクは、次のチャンクとつなげてから読む。
+pub async fn for_each_data<F>(
+    mut response: reqwest::Response,
+    mut on_data: F,
+) -> Result<(), LlmError>
+where
+    F: FnMut(&str) -> Result<(), LlmError>,
+{
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            handle_line(&line, &mut on_data)?;
+        }
+    }
+    // 最後の行が改行で終わっていない場合
+    handle_line(&buffer, &mut on_data)
+}
+
+fn handle_line<F>(line: &[u8], on_data: &mut F) -> Result<(), LlmError>
+where
+    F: FnMut(&str) -> Result<(), LlmError>,
+{
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end_matches(['\r', '\n']);
+    match line.strip_prefix("data:") {
+        Some(data) => on_data(data.strip_prefix(' ').unwrap_or(data)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // 本文を chunked で1チャンクずつ送るサーバーに接続する
+    async fn chunked_response(chunks: Vec<&'static [u8]>) -> reqwest::Response {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\
+                      transfer-encoding: chunked\r\nconnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            for chunk in chunks {
+                socket
+                    .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                    .await
+                    .unwrap();
+                socket.write_all(chunk).await.unwrap();
+                socket.write_all(b"\r\n").await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            socket.write_all(b"0\r\n\r\n").await.unwrap();
+        });
+
+        reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .unwrap()
+            .get(format!("http://{}", addr))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn joins_lines_and_characters_split_across_chunks() {
+        let response = chunked_response(vec![
+            b"data: {\"a\":",
+            b"1}\r\n\r\nevent: ping\n: comment\ndata: caf",
+            b"\xc3",
+            b"\xa9\ndata:no-space\n",
+            b"data: last",
+        ])
+        .await;
+
+        let mut data = Vec::new();
+        for_each_data(response, |line| {
+            data.push(line.to_string());
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(data, vec!["{\"a\":1}", "café", "no-space", "last"]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_callback_error() {
+        let response = chunked_response(vec![b"data: one\ndata: two\ndata: three\n"]).await;
+
+        let mut seen = 0;
+        let result = for_each_data(response, |line| {
+            seen += 1;
+            if line == "two" {
+                Err(LlmError::InvalidResponse("bad event".to_string()))
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        assert!(matches!(result, Err(LlmError::InvalidResponse(_))));
+        assert_eq!(seen, 2);
+    }
+}
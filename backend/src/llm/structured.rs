@@ -0,0 +1,386 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{AiError, AiTask, Completion, LlmProvider, TokenUsage};
+
+// 修復用プロンプトに含める前回の応答の最大文字数
+const MAX_ECHOED_RESPONSE_CHARS: usize = 4000;
+
+/// JSON で受け取る AI の出力
+///
+/// スキーマはモデルに渡して出力形式を強制し、`validate` で中身の不備 (空の単語など) を弾く。
+pub trait StructuredOutput: DeserializeOwned {
+    /// 応答スキーマ (OpenAPI のサブセット。Gemini の responseSchema と同じ形式)
+    fn schema() -> Value;
+
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// スキーマ付きで生成し、検証に失敗したら理由を添えて1回だけ作り直させる
+///
+/// 使用量は作り直しの分も合算して返す。失敗した場合も使った分は `AiError::usage` で分かる。
+pub async fn generate_structured<T: StructuredOutput>(
+    llm: &dyn LlmProvider,
+    task: AiTask,
+    prompt: &str,
+) -> Result<(T, TokenUsage), AiError> {
+    let response = llm.generate_json(task, prompt, &T::schema()).await?;
+    finish_structured(llm, task, prompt, response).await
+}
+
+/// 生成済みの応答を検証し、使えなければ作り直させる (ストリーミングでも使う)
+pub async fn finish_structured<T: StructuredOutput>(
+    llm: &dyn LlmProvider,
+    task: AiTask,
+    prompt: &str,
+    response: Completion,
+) -> Result<(T, TokenUsage), AiError> {
+    let reason = match parse_output::<T>(&response.text) {
+        Ok(output) => return Ok((output, response.usage)),
+        Err(reason) => reason,
+    };
+
+    println!(
+        "AI response for {} was unusable ({}), asking for a repaired response",
+        task.as_str(),
+        reason
+    );
+    let schema = T::schema();
+    let repair_prompt = format!(
+        "{}\n\nYour previous response could not be used: {}\n\nPrevious response:\n{}\n\n\
+         Respond again with only a JSON object that matches this schema, without any explanation or code fences:\n{}",
+        prompt,
+        reason,
+        truncate(&response.text, MAX_ECHOED_RESPONSE_CHARS),
+        schema
+    );
+    // 作り直しに失敗しても最初の応答の分は使っているので、使用量を付けて返す
+    let repaired = match llm.generate_json(task, &repair_prompt, &schema).await {
+        Ok(repaired) => repaired,
+        Err(err) => {
+            return Err(AiError::InvalidOutput {
+                reason: format!("{} (the repair request failed: {})", reason, err),
+                usage: response.usage,
+            })
+        }
+    };
+    let usage = response.usage + repaired.usage;
+    let output = parse_output::<T>(&repaired.text)
+        .map_err(|reason| AiError::InvalidOutput { reason, usage })?;
+    Ok((output, usage))
+}
+
+/// 応答テキストから JSON を取り出し、型に変換して検証する
+pub fn parse_output<T: StructuredOutput>(text: &str) -> Result<T, String> {
+    let value = extract_json(text).ok_or("the response does not contain a JSON object")?;
+    let output: T = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    output.validate()?;
+    Ok(output)
+}
+
+/// 応答に含まれる最初の JSON オブジェクト
+///
+/// 前後の説明文やコードブロックの囲みは無視し、閉じ括弧の直前のカンマは取り除いてから読む。
+pub fn extract_json(text: &str) -> Option<Value> {
+    text.char_indices()
+        .filter(|&(_, c)| c == '{')
+        .find_map(|(start, _)| {
+            let end = matching_brace(text, start)?;
+            let candidate = remove_trailing_commas(&text[start..=end]);
+            serde_json::from_str::<Value>(&candidate)
+                .ok()
+                .filter(Value::is_object)
+        })
+}
+
+/// `start` の `{` に対応する `}` の位置 (文字列リテラル内の括弧は数えない)
+pub(crate) fn matching_brace(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `[1, 2,]` や `{"a": 1,}` のような閉じ括弧直前のカンマを取り除く
+fn remove_trailing_commas(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut result = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 生成途中の JSON から、1つの文字列フィールドの値を少しずつ取り出す
+///
+/// `{"explanation": "Affect is ...` まで届いた時点で `Affect is ...` を返し、
+/// 以降は前回から増えた分だけを返す。エスケープの途中で切れている場合は続きを待つ。
+#[derive(Debug)]
+pub struct StringFieldStream {
+    field: &'static str,
+    text: String,
+    /// 値の開始位置 (開き引用符の次)
+    start: Option<usize>,
+    /// 返し終えた位置
+    emitted: usize,
+    finished: bool,
+}
+
+impl StringFieldStream {
+    pub fn new(field: &'static str) -> Self {
+        Self {
+            field,
+            text: String::new(),
+            start: None,
+            emitted: 0,
+            finished: false,
+        }
+    }
+
+    /// 届いた断片を追加し、新たに読めたフィールドの値を返す
+    pub fn push(&mut self, delta: &str) -> String {
+        self.text.push_str(delta);
+        if self.finished {
+            return String::new();
+        }
+        if self.start.is_none() {
+            self.start = self.find_value_start();
+            match self.start {
+                Some(start) => self.emitted = start,
+                None => return String::new(),
+            }
+        }
+
+        let raw = &self.text[self.emitted..];
+        let mut end = 0;
+        let mut chars = raw.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.finished = true;
+                    break;
+                }
+                '\\' => {
+                    let escape_len = match chars.peek() {
+                        None => break,
+                        Some((_, 'u')) => 6,
+                        Some(_) => 2,
+                    };
+                    if raw.len() < i + escape_len {
+                        break;
+                    }
+                    // サロゲートペアの前半だけでは文字にならないので後半を待つ
+                    let escape = &raw[i..i + escape_len];
+                    if escape_len == 6 && is_high_surrogate(escape) {
+                        if raw.len() < i + 12 {
+                            break;
+                        }
+                        end = i + 12;
+                    } else {
+                        end = i + escape_len;
+                    }
+                    while chars.peek().is_some_and(|&(j, _)| j < end) {
+                        chars.next();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            end = i + c.len_utf8();
+        }
+
+        let segment = &raw[..end];
+        self.emitted += end;
+        serde_json::from_str::<String>(&format!("\"{}\"", segment)).unwrap_or_default()
+    }
+
+    /// `"field"` の後の `:` と開き引用符を探す
+    fn find_value_start(&self) -> Option<usize> {
+        let key = format!("\"{}\"", self.field);
+        let mut from = 0;
+        while let Some(pos) = self.text[from..].find(&key) {
+            let after_key = from + pos + key.len();
+            let rest = self.text[after_key..].trim_start();
+            if let Some(rest) = rest.strip_prefix(':') {
+                let value = rest.trim_start();
+                if let Some(value) = value.strip_prefix('"') {
+                    return Some(self.text.len() - value.len());
+                }
+                // 値がまだ届いていない
+                if value.is_empty() {
+                    return None;
+                }
+            } else if rest.is_empty() {
+                return None;
+            }
+            from = after_key;
+        }
+        None
+    }
+}
+
+fn is_high_surrogate(escape: &str) -> bool {
+    u16::from_str_radix(&escape[2..], 16).is_ok_and(|code| (0xD800..0xDC00).contains(&code))
+}
+
+fn truncate(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// 空でないことを確かめる (検証用)
+pub fn require_text(field: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        Err(format!("`{}` must not be empty", field))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockProvider;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize)]
+    struct Help {
+        explanation: String,
+        examples: Vec<String>,
+    }
+
+    impl StructuredOutput for Help {
+        fn schema() -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "explanation": { "type": "string" },
+                    "examples": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["explanation", "examples"]
+            })
+        }
+
+        fn validate(&self) -> Result<(), String> {
+            require_text("explanation", &self.explanation)
+        }
+    }
+
+    #[test]
+    fn extracts_json_after_prose_and_inside_fences() {
+        let text = "Sure! Here you go:\n```json\n{\"explanation\": \"x\", \"examples\": []}\n```";
+        assert_eq!(
+            extract_json(text),
+            Some(json!({"explanation": "x", "examples": []}))
+        );
+    }
+
+    #[test]
+    fn skips_braces_that_are_not_json_objects() {
+        let text = "Use {curly} braces like this: {\"a\": \"} {\", \"b\": [1, 2,],}";
+        assert_eq!(extract_json(text), Some(json!({"a": "} {", "b": [1, 2]})));
+        assert_eq!(extract_json("no json here"), None);
+        assert_eq!(extract_json("{\"unterminated\": 1"), None);
+    }
+
+    #[test]
+    fn removes_trailing_commas_outside_strings() {
+        assert_eq!(
+            remove_trailing_commas("{\"a\": [1, 2, ], \"b\": \",}\",\n}"),
+            "{\"a\": [1, 2 ], \"b\": \",}\"\n}"
+        );
+        assert_eq!(remove_trailing_commas("{\"a\": 1}"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn parse_output_validates_the_result() {
+        let help: Help =
+            parse_output("{\"explanation\": \"affect is a verb\", \"examples\": [\"a\",]}")
+                .unwrap();
+        assert_eq!(help.examples, vec!["a"]);
+        assert!(parse_output::<Help>("{\"explanation\": \" \", \"examples\": []}").is_err());
+        assert!(parse_output::<Help>("{\"examples\": []}").is_err());
+    }
+
+    #[tokio::test]
+    async fn generates_structured_output_with_the_mock_provider() {
+        let prompt = "Answer in JSON:\n{\"explanation\": \"an example\", \"examples\": [\"one\"]}";
+        let (help, usage): (Help, _) =
+            generate_structured(&MockProvider::new(), AiTask::Help, prompt)
+                .await
+                .unwrap();
+        assert_eq!(help.explanation, "an example");
+        assert!(usage.output_tokens > 0);
+    }
+
+    #[tokio::test]
+    async fn invalid_output_carries_the_usage_of_both_attempts() {
+        // モックは作り直しのプロンプトでも同じ (空の explanation の) JSON を返す
+        let prompt = "Answer in JSON:\n{\"explanation\": \" \", \"examples\": []}";
+        let err = generate_structured::<Help>(&MockProvider::new(), AiTask::Help, prompt)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AiError::InvalidOutput { .. }));
+        let single_call = prompt.chars().count().div_ceil(4) as u32;
+        assert!(err.usage().input_tokens > 2 * single_call);
+        assert!(err.usage().output_tokens > 0);
+    }
+
+    #[test]
+    fn streams_a_string_field_across_deltas() {
+        let mut stream = StringFieldStream::new("explanation");
+        assert_eq!(stream.push("{\"examples\": [\"explanation\"], \"expla"), "");
+        assert_eq!(stream.push("nation\": "), "");
+        assert_eq!(stream.push("\"Affect is "), "Affect is ");
+        assert_eq!(stream.push("a verb\\"), "a verb");
+        assert_eq!(stream.push("n, effect"), "\n, effect");
+        assert_eq!(stream.push(" \\u00e9"), " é");
+        assert_eq!(stream.push(" \\ud83d"), " ");
+        assert_eq!(stream.push("\\ude00 done\", \"other\": \"x\"}"), "😀 done");
+        assert_eq!(stream.push("more"), "");
+    }
+}
@@ -3,7 +3,7 @@ use shuttle_axum::{
     axum::{
         http::Method,
         middleware::from_fn_with_state,
-        routing::{get, post},
+        routing::{get, post, put},
         Router,
     },
     ShuttleAxum,
@@ -13,18 +13,50 @@ use tower_http::cors::{Any, CorsLayer};
 
 mod auth_middleware;
 mod handlers;
+mod llm;
 mod models;
+mod services;
 
 use models::AppState;
 
 use auth_middleware::auth::auth_middleware;
 use handlers::ai_handler::{
-    analyze_conversation_handler, vocabulary_help_handler, word_suggestions_handler,
+    analyze_conversation_handler, analyze_conversation_stream_handler, generate_examples_handler,
+    get_ai_usage_handler, vocabulary_help_handler, vocabulary_help_stream_handler,
+    word_autofill_handler, word_suggestions_handler,
 };
 use handlers::auth_handler::{get_current_user, github_oauth_callback, google_oauth_callback};
+use handlers::exam_handler::{
+    answer_exam_handler, finish_exam_handler, get_exam_handler, list_exams_handler,
+    start_exam_handler,
+};
+use handlers::goal_handler::{get_goals_handler, get_streak_handler, update_goals_handler};
+use handlers::leech_handler::{
+    get_leech_settings_handler, get_leeches_handler, regenerate_leech_aids_handler,
+    reset_leech_handler, update_leech_settings_handler,
+};
+use handlers::leitner_handler::{
+    get_leitner_boxes_handler, get_leitner_settings_handler, leitner_answer_handler,
+    update_leitner_settings_handler,
+};
+use handlers::placement_handler::{
+    answer_placement_handler, get_placement_handler, start_placement_handler,
+};
+use handlers::quiz_handler::{
+    check_cloze_handler, check_sentence_handler, create_cloze_handler, grade_answer_handler,
+};
+use handlers::study_handler::{
+    get_forecast_handler, get_study_settings_handler, get_today_plan_handler,
+    update_study_settings_handler,
+};
+use handlers::tutor_handler::{
+    end_tutor_session_handler, get_tutor_session_handler, list_tutor_sessions_handler,
+    send_tutor_message_handler, start_tutor_session_handler,
+};
 use handlers::word_handler::{
-    create_word_handler, delete_word_handler, get_word_handler, get_words_handler,
-    update_word_handler,
+    add_example_handler, create_word_from_suggestion_handler, create_word_handler,
+    delete_word_handler, export_words_handler, get_word_handler, get_word_stats_handler,
+    get_words_handler, update_word_handler, update_word_status_handler,
 };
 
 async fn health_check() -> &'static str {
@@ -65,15 +97,22 @@ async fn main(
         .get("AUTH_GOOGLE_SECRET")
         .context("AUTH_GOOGLE_SECRET not found")?;
 
-    let gemini_api_key = secrets
-        .get("GEMINI_API_KEY")
-        .context("GEMINI_API_KEY not found")?;
+    // AIプロバイダ・モデル・生成パラメータ。キーが無くてもローカル採点などは動作させる
+    let ai_config = llm::AiConfig::from_lookup(|key| secrets.get(key))
+        .map_err(|message| anyhow::anyhow!(message))?;
 
     // Log that secrets were loaded successfully (without revealing the actual values)
     println!("✓ AUTH_SECRET loaded");
     println!("✓ GitHub OAuth credentials loaded");
     println!("✓ Google OAuth credentials loaded");
-    println!("✓ Gemini API key loaded");
+    if ai_config.provider == llm::ProviderKind::Gemini && ai_config.api_key.is_none() {
+        println!("⚠ GEMINI_API_KEY not set, AI features are disabled");
+    }
+    let ai_cache_ttl = ai_config.cache_ttl;
+    let ai_quota = ai_config.quota;
+    let http_client = llm::build_http_client(&ai_config).context("Failed to build HTTP client")?;
+    let llm = llm::build_provider(ai_config, http_client);
+    println!("✓ AI provider: {}", llm.name());
 
     // データベース接続プールを作成
     let pool = sqlx::PgPool::connect(&connection_string)
@@ -86,6 +125,13 @@ async fn main(
         .await
         .context("Failed to run migrations")?;
 
+    // 期限切れのAIキャッシュを掃除する (失敗しても起動は続ける)
+    match services::ai_cache::purge_expired(&pool).await {
+        Ok(0) => {}
+        Ok(count) => println!("✓ Purged {} expired AI cache entries", count),
+        Err(e) => println!("⚠ Failed to purge AI cache: {}", e),
+    }
+
     // Create AppState with all the necessary components
     let app_state = AppState::new(
         pool,
@@ -94,7 +140,9 @@ async fn main(
         github_client_secret,
         google_client_id,
         google_client_secret,
-        gemini_api_key,
+        llm,
+        ai_cache_ttl,
+        ai_quota,
     );
 
     // CORS設定
@@ -123,12 +171,94 @@ async fn main(
                 .put(update_word_handler)
                 .delete(delete_word_handler),
         )
+        .route("/api/words/export", get(export_words_handler))
+        .route("/api/words/autofill", post(word_autofill_handler))
+        .route(
+            "/api/words/from-suggestion",
+            post(create_word_from_suggestion_handler),
+        )
+        .route("/api/words/stats", get(get_word_stats_handler))
+        .route("/api/words/{id}/status", put(update_word_status_handler))
+        .route("/api/words/{id}/examples", post(add_example_handler))
+        .route(
+            "/api/words/{id}/examples/generate",
+            post(generate_examples_handler),
+        )
+        .route(
+            "/api/words/{id}/leech-aids",
+            post(regenerate_leech_aids_handler),
+        )
+        .route("/api/words/{id}/leech/reset", post(reset_leech_handler))
         .route(
             "/api/conversation-analysis",
             post(analyze_conversation_handler),
         )
+        .route(
+            "/api/conversation-analysis/stream",
+            post(analyze_conversation_stream_handler),
+        )
         .route("/api/vocabulary-help", post(vocabulary_help_handler))
+        .route(
+            "/api/vocabulary-help/stream",
+            post(vocabulary_help_stream_handler),
+        )
         .route("/api/word-suggestions", post(word_suggestions_handler))
+        .route("/api/ai/usage", get(get_ai_usage_handler))
+        .route("/api/quiz/grade", post(grade_answer_handler))
+        .route("/api/quiz/cloze", post(create_cloze_handler))
+        .route("/api/quiz/cloze/{id}/check", post(check_cloze_handler))
+        .route("/api/quiz/sentence", post(check_sentence_handler))
+        .route(
+            "/api/goals",
+            get(get_goals_handler).put(update_goals_handler),
+        )
+        .route("/api/streak", get(get_streak_handler))
+        .route(
+            "/api/leitner/settings",
+            get(get_leitner_settings_handler).put(update_leitner_settings_handler),
+        )
+        .route("/api/leeches", get(get_leeches_handler))
+        .route(
+            "/api/leeches/settings",
+            get(get_leech_settings_handler).put(update_leech_settings_handler),
+        )
+        .route("/api/leitner/boxes", get(get_leitner_boxes_handler))
+        .route(
+            "/api/leitner/words/{id}/answer",
+            post(leitner_answer_handler),
+        )
+        .route("/api/study/today", get(get_today_plan_handler))
+        .route("/api/study/forecast", get(get_forecast_handler))
+        .route(
+            "/api/study/settings",
+            get(get_study_settings_handler).put(update_study_settings_handler),
+        )
+        .route(
+            "/api/exams",
+            get(list_exams_handler).post(start_exam_handler),
+        )
+        .route("/api/exams/{id}", get(get_exam_handler))
+        .route("/api/exams/{id}/answers", post(answer_exam_handler))
+        .route("/api/exams/{id}/finish", post(finish_exam_handler))
+        .route("/api/placement", post(start_placement_handler))
+        .route("/api/placement/{id}", get(get_placement_handler))
+        .route(
+            "/api/placement/{id}/answers",
+            post(answer_placement_handler),
+        )
+        .route(
+            "/api/tutor/sessions",
+            get(list_tutor_sessions_handler).post(start_tutor_session_handler),
+        )
+        .route("/api/tutor/sessions/{id}", get(get_tutor_session_handler))
+        .route(
+            "/api/tutor/sessions/{id}/messages",
+            post(send_tutor_message_handler),
+        )
+        .route(
+            "/api/tutor/sessions/{id}/end",
+            post(end_tutor_session_handler),
+        )
         .layer(from_fn_with_state(app_state.clone(), auth_middleware));
 
     let router = Router::new()
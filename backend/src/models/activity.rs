@@ -0,0 +1,42 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// activity_log.kind の値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    WordAdded,
+    Review,
+}
+
+impl ActivityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::WordAdded => "word_added",
+            ActivityKind::Review => "review",
+        }
+    }
+}
+
+pub struct Activity;
+
+impl Activity {
+    pub async fn record(
+        pool: &PgPool,
+        user_id: Uuid,
+        kind: ActivityKind,
+        word_id: Option<&str>,
+        correct: Option<bool>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO activity_log (user_id, kind, word_id, correct) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .bind(word_id)
+        .bind(correct)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use sqlx::PgPool;
 
+use crate::llm::{AiQuota, LlmProvider};
+
 /// Application state that holds all shared resources
 #[derive(Clone)]
 pub struct AppState {
@@ -15,11 +20,16 @@ pub struct AppState {
     pub google_client_id: String,
     /// Google OAuth client secret
     pub google_client_secret: String,
-    /// Gemini API Key
-    pub gemini_api_key: String,
+    /// AI provider (Gemini, OpenAI-compatible or mock)
+    pub llm: Arc<dyn LlmProvider>,
+    /// How long AI responses are cached (zero disables the cache)
+    pub ai_cache_ttl: Duration,
+    /// Per-user AI token quotas
+    pub ai_quota: AiQuota,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pool: PgPool,
         auth_secret: String,
@@ -27,7 +37,9 @@ impl AppState {
         github_client_secret: String,
         google_client_id: String,
         google_client_secret: String,
-        gemini_api_key: String,
+        llm: Arc<dyn LlmProvider>,
+        ai_cache_ttl: Duration,
+        ai_quota: AiQuota,
     ) -> Self {
         Self {
             pool,
@@ -36,7 +48,9 @@ impl AppState {
             github_client_secret,
             google_client_id,
             google_client_secret,
-            gemini_api_key,
+            llm,
+            ai_cache_ttl,
+            ai_quota,
         }
     }
 }
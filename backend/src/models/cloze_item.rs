@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClozeItem {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub word_id: String,
+    pub headword: String,
+    pub text: String,                            // 空欄入りの文
+    pub answers: sqlx::types::Json<Vec<String>>, // 空欄に入る語 (文中の形)
+    pub source: String,                          // example | ai
+    pub created_at: DateTime<Utc>,
+}
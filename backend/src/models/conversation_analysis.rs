@@ -0,0 +1,45 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// 保存した会話分析 (提案をあとから単語として登録できるようにする)
+pub struct ConversationAnalysis;
+
+impl ConversationAnalysis {
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        conversation_text: &str,
+        result: &Value,
+        prompt_version: &str,
+    ) -> Result<Uuid, sqlx::Error> {
+        let (id,): (Uuid,) = sqlx::query_as(
+            "INSERT INTO conversation_analyses (user_id, conversation_text, result, prompt_version)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id",
+        )
+        .bind(user_id)
+        .bind(conversation_text)
+        .bind(result)
+        .bind(prompt_version)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// 分析結果の JSON (他のユーザーの分析なら None)
+    pub async fn find_result(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<Value>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT result FROM conversation_analyses WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::NaiveDate;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::models::user_settings::UserSettings;
+use crate::services::streak::{DailyGoals, DayActivity};
+
+/// 終わった日の学習量と、その日に有効だった目標
+///
+/// 目標やタイムゾーンを後から変えても過去のストリークが変わらないよう、日が終わった後の
+/// 最初の集計で固定する。
+#[derive(Debug, Clone, FromRow)]
+pub struct DailyGoalResult {
+    pub day: NaiveDate,
+    pub new_words: i32,
+    pub reviews: i32,
+    pub new_words_goal: i32,
+    pub reviews_goal: i32,
+}
+
+impl DailyGoalResult {
+    pub fn goal_met(&self) -> bool {
+        let goals = DailyGoals {
+            new_words: self.new_words_goal as i64,
+            reviews: self.reviews_goal as i64,
+        };
+        goals.is_met(&DayActivity {
+            new_words: self.new_words as i64,
+            reviews: self.reviews as i64,
+        })
+    }
+
+    /// `today` より前でまだ記録していない日を、`settings` の目標とタイムゾーンで記録する
+    ///
+    /// 目標やタイムゾーンを変える前にも呼び、変更前の設定で過去の日を固定しておく。
+    pub async fn record_finished_days(
+        pool: &PgPool,
+        settings: &UserSettings,
+        today: NaiveDate,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO daily_goal_results (user_id, day, new_words, reviews, new_words_goal, reviews_goal)
+             SELECT $1, day,
+                    COUNT(*) FILTER (WHERE kind = 'word_added'),
+                    COUNT(*) FILTER (WHERE kind = 'review'),
+                    $4, $5
+             FROM (
+                 SELECT ((occurred_at AT TIME ZONE $2) - make_interval(hours => $3))::date AS day, kind
+                 FROM activity_log
+                 WHERE user_id = $1
+             ) AS activity
+             WHERE day < $6
+             GROUP BY day
+             ON CONFLICT (user_id, day) DO NOTHING",
+        )
+        .bind(settings.user_id)
+        .bind(&settings.time_zone)
+        .bind(settings.day_rollover_hour)
+        .bind(settings.daily_new_words_goal)
+        .bind(settings.daily_reviews_goal)
+        .bind(today)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 記録済みの日 (古い順)
+    pub async fn list_for_user(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<DailyGoalResult>, sqlx::Error> {
+        sqlx::query_as::<_, DailyGoalResult>(
+            "SELECT day, new_words, reviews, new_words_goal, reviews_goal
+             FROM daily_goal_results
+             WHERE user_id = $1
+             ORDER BY day",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// 締め切り後も回答を受け付ける猶予 (秒)。通信の遅延を吸収する
+pub const ANSWER_GRACE_SECONDS: i64 = 5;
+
+/// exams.status の値
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExamStatus {
+    InProgress, // 受験中
+    Finished,   // 時間内に終了
+    Expired,    // 制限時間切れで終了
+}
+
+impl ExamStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExamStatus::InProgress => "in_progress",
+            ExamStatus::Finished => "finished",
+            ExamStatus::Expired => "expired",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Exam {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub categories: sqlx::types::Json<Vec<String>>, // 出題対象のカテゴリ (空なら全単語)
+    pub question_count: i32,
+    pub time_limit_seconds: i32,
+    pub status: String,     // ExamStatus
+    pub score: Option<i32>, // 正解数 (終了後に確定)
+    pub started_at: DateTime<Utc>,
+    pub deadline_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExamQuestion {
+    pub exam_id: Uuid,
+    pub position: i32, // 1始まりの出題順
+    pub word_id: String,
+    pub kind: String,   // sentence_completion | meaning
+    pub prompt: String, // 空欄入りの文、または見出し語
+    pub choices: sqlx::types::Json<Vec<String>>,
+    pub correct_index: i32,
+    pub selected_index: Option<i32>,
+    pub is_correct: Option<bool>,
+    pub answered_at: Option<DateTime<Utc>>,
+}
+
+impl Exam {
+    pub fn is_in_progress(&self) -> bool {
+        self.status == ExamStatus::InProgress.as_str()
+    }
+
+    /// 猶予を含めて制限時間を過ぎているか
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        now > self.deadline_at + chrono::Duration::seconds(ANSWER_GRACE_SECONDS)
+    }
+
+    pub async fn find_for_user(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<Exam>, sqlx::Error> {
+        sqlx::query_as::<_, Exam>("SELECT * FROM exams WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn questions(&self, pool: &PgPool) -> Result<Vec<ExamQuestion>, sqlx::Error> {
+        sqlx::query_as::<_, ExamQuestion>(
+            "SELECT * FROM exam_questions WHERE exam_id = $1 ORDER BY position",
+        )
+        .bind(self.id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// 試験を終了して採点する。未回答の問題は不正解として扱う
+    ///
+    /// すでに終了している場合は何もせず現在の状態を返す。
+    pub async fn finalize(&self, pool: &PgPool, status: ExamStatus) -> Result<Exam, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE exam_questions SET is_correct = false
+             WHERE exam_id = $1 AND is_correct IS NULL",
+        )
+        .bind(self.id)
+        .execute(&mut *tx)
+        .await?;
+
+        // 時間切れの場合、終了時刻は締め切り時刻とする
+        let finished = sqlx::query_as::<_, Exam>(
+            "UPDATE exams SET
+                status = $2,
+                score = (SELECT COUNT(*) FROM exam_questions WHERE exam_id = $1 AND is_correct)::int,
+                finished_at = LEAST(NOW(), deadline_at)
+             WHERE id = $1 AND status = 'in_progress'
+             RETURNING *",
+        )
+        .bind(self.id)
+        .bind(status.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        match finished {
+            Some(exam) => Ok(exam),
+            None => {
+                sqlx::query_as::<_, Exam>("SELECT * FROM exams WHERE id = $1")
+                    .bind(self.id)
+                    .fetch_one(pool)
+                    .await
+            }
+        }
+    }
+
+    /// 制限時間を過ぎた受験中の試験を時間切れとして終了させる
+    pub async fn expire_if_overdue(self, pool: &PgPool) -> Result<Exam, sqlx::Error> {
+        if self.is_in_progress() && self.is_overdue(Utc::now()) {
+            self.finalize(pool, ExamStatus::Expired).await
+        } else {
+            Ok(self)
+        }
+    }
+}
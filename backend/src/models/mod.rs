@@ -1,5 +1,14 @@
+pub mod activity;
 pub mod app_state;
+pub mod cloze_item;
+pub mod conversation_analysis;
+pub mod daily_goal_result;
+pub mod exam;
+pub mod placement;
+pub mod study_plan;
+pub mod tutor_session;
 pub mod user;
+pub mod user_settings;
 pub mod word;
 
 pub use app_state::AppState;
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PlacementTest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: String, // in_progress | finished
+    pub cefr_level: Option<String>,
+    pub vocab_estimate: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PlacementItemRow {
+    pub test_id: Uuid,
+    pub position: i32,
+    pub band: i32,    // 0始まりの頻度帯 (1000語ごと)
+    pub kind: String, // yes_no | multiple_choice
+    pub word: String,
+    pub is_real: bool, // false なら擬似語
+    pub choices: sqlx::types::Json<Vec<String>>,
+    pub correct_index: Option<i32>,
+    pub correct: Option<bool>,
+    pub answered_at: Option<DateTime<Utc>>,
+}
+
+impl PlacementTest {
+    pub fn is_in_progress(&self) -> bool {
+        self.status == "in_progress"
+    }
+
+    pub async fn find_for_user(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<PlacementTest>, sqlx::Error> {
+        sqlx::query_as::<_, PlacementTest>(
+            "SELECT * FROM placement_tests WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn items(&self, pool: &PgPool) -> Result<Vec<PlacementItemRow>, sqlx::Error> {
+        sqlx::query_as::<_, PlacementItemRow>(
+            "SELECT * FROM placement_items WHERE test_id = $1 ORDER BY position",
+        )
+        .bind(self.id)
+        .fetch_all(pool)
+        .await
+    }
+}
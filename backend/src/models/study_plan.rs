@@ -0,0 +1,67 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StudyPlan {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub plan_date: NaiveDate, // ユーザーのローカル日付
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StudyPlanItem {
+    pub plan_id: Uuid,
+    pub position: i32, // 1始まりの学習順
+    pub word_id: String,
+    pub kind: String, // review | new
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl StudyPlan {
+    pub async fn find_for_date(
+        pool: &PgPool,
+        user_id: Uuid,
+        plan_date: NaiveDate,
+    ) -> Result<Option<StudyPlan>, sqlx::Error> {
+        sqlx::query_as::<_, StudyPlan>(
+            "SELECT * FROM study_plans WHERE user_id = $1 AND plan_date = $2",
+        )
+        .bind(user_id)
+        .bind(plan_date)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn items(&self, pool: &PgPool) -> Result<Vec<StudyPlanItem>, sqlx::Error> {
+        sqlx::query_as::<_, StudyPlanItem>(
+            "SELECT * FROM study_plan_items WHERE plan_id = $1 ORDER BY position",
+        )
+        .bind(self.id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// その日のプランに含まれる単語を学習済みにする (プランが無ければ何もしない)
+    pub async fn mark_completed(
+        pool: &PgPool,
+        user_id: Uuid,
+        plan_date: NaiveDate,
+        word_id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE study_plan_items SET completed_at = NOW()
+             WHERE word_id = $3 AND completed_at IS NULL
+               AND plan_id = (SELECT id FROM study_plans WHERE user_id = $1 AND plan_date = $2)",
+        )
+        .bind(user_id)
+        .bind(plan_date)
+        .bind(word_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
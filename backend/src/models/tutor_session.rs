@@ -0,0 +1,212 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+/// tutor_sessions.status の値
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TutorSessionStatus {
+    Active, // 会話中
+    Ended,  // 終了 (会話分析済み)
+}
+
+impl TutorSessionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TutorSessionStatus::Active => "active",
+            TutorSessionStatus::Ended => "ended",
+        }
+    }
+}
+
+/// tutor_messages.role の値
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TutorRole {
+    Learner, // 学習者
+    Tutor,   // AI のチューター
+}
+
+impl TutorRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TutorRole::Learner => "learner",
+            TutorRole::Tutor => "tutor",
+        }
+    }
+
+    /// 会話の書き起こしでの話者名
+    pub fn speaker(&self) -> &'static str {
+        match self {
+            TutorRole::Learner => "Learner",
+            TutorRole::Tutor => "Tutor",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [TutorRole::Learner, TutorRole::Tutor]
+            .into_iter()
+            .find(|role| role.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TutorSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub topic: Option<String>,
+    pub cefr_level: String,                          // 開始時の学習者のレベル
+    pub focus_words: sqlx::types::Json<Vec<String>>, // 会話に織り込む復習予定の単語
+    pub status: String,                              // TutorSessionStatus
+    pub analysis_id: Option<Uuid>,                   // 終了時の会話分析
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TutorMessage {
+    pub session_id: Uuid,
+    pub position: i32, // 1始まりの発言順
+    pub role: String,  // TutorRole
+    pub content: String,
+    pub used_words: sqlx::types::Json<Vec<String>>, // 発言に含まれる focus_words
+    pub created_at: DateTime<Utc>,
+}
+
+impl TutorSession {
+    pub fn is_active(&self) -> bool {
+        self.status == TutorSessionStatus::Active.as_str()
+    }
+
+    pub async fn create(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        topic: Option<&str>,
+        cefr_level: &str,
+        focus_words: &[String],
+    ) -> Result<TutorSession, sqlx::Error> {
+        sqlx::query_as::<_, TutorSession>(
+            "INSERT INTO tutor_sessions (user_id, topic, cefr_level, focus_words)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+        )
+        .bind(user_id)
+        .bind(topic)
+        .bind(cefr_level)
+        .bind(serde_json::to_value(focus_words).unwrap_or_default())
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn find_for_user(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<TutorSession>, sqlx::Error> {
+        sqlx::query_as::<_, TutorSession>(
+            "SELECT * FROM tutor_sessions WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// セッションの行をロックして取得する (トランザクション内で使う)
+    ///
+    /// 同じセッションへの発言の追加や終了は、このロックを取ってから行う。
+    /// AI の呼び出しの間はロックしないこと (接続を長く占有してしまう)。
+    pub async fn lock_for_user(
+        conn: &mut PgConnection,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<TutorSession>, sqlx::Error> {
+        sqlx::query_as::<_, TutorSession>(
+            "SELECT * FROM tutor_sessions WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+    }
+
+    /// 新しい順のセッション一覧
+    pub async fn list_for_user(
+        pool: &PgPool,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TutorSession>, sqlx::Error> {
+        sqlx::query_as::<_, TutorSession>(
+            "SELECT * FROM tutor_sessions WHERE user_id = $1 ORDER BY started_at DESC LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn messages(
+        &self,
+        executor: impl PgExecutor<'_>,
+    ) -> Result<Vec<TutorMessage>, sqlx::Error> {
+        sqlx::query_as::<_, TutorMessage>(
+            "SELECT * FROM tutor_messages WHERE session_id = $1 ORDER BY position",
+        )
+        .bind(self.id)
+        .fetch_all(executor)
+        .await
+    }
+
+    /// 最後の発言の順番 (発言が無ければ 0)
+    pub async fn last_position(&self, executor: impl PgExecutor<'_>) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(MAX(position), 0) FROM tutor_messages WHERE session_id = $1",
+        )
+        .bind(self.id)
+        .fetch_one(executor)
+        .await
+    }
+
+    /// 発言を末尾に追加する
+    ///
+    /// 発言順は既存の最大値 + 1 なので、`lock_for_user` でロックしたトランザクション内で呼ぶ。
+    pub async fn add_message(
+        &self,
+        conn: &mut PgConnection,
+        role: TutorRole,
+        content: &str,
+        used_words: &[String],
+    ) -> Result<TutorMessage, sqlx::Error> {
+        sqlx::query_as::<_, TutorMessage>(
+            "INSERT INTO tutor_messages (session_id, position, role, content, used_words)
+             SELECT $1, COALESCE(MAX(position), 0) + 1, $2, $3, $4
+             FROM tutor_messages WHERE session_id = $1
+             RETURNING *",
+        )
+        .bind(self.id)
+        .bind(role.as_str())
+        .bind(content)
+        .bind(serde_json::to_value(used_words).unwrap_or_default())
+        .fetch_one(conn)
+        .await
+    }
+
+    /// セッションを終了し、会話分析を結びつける (`lock_for_user` でロックしたトランザクション内で呼ぶ)
+    pub async fn end(
+        &self,
+        conn: &mut PgConnection,
+        analysis_id: Option<Uuid>,
+    ) -> Result<TutorSession, sqlx::Error> {
+        sqlx::query_as::<_, TutorSession>(
+            "UPDATE tutor_sessions SET status = $2, analysis_id = $3, ended_at = NOW()
+             WHERE id = $1
+             RETURNING *",
+        )
+        .bind(self.id)
+        .bind(TutorSessionStatus::Ended.as_str())
+        .bind(analysis_id)
+        .fetch_one(conn)
+        .await
+    }
+}
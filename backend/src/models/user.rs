@@ -11,10 +11,59 @@ pub struct User {
     pub image: Option<String>,
     pub provider: String,
     pub provider_id: String,
+    pub cefr_level: Option<String>, // レベル判定テストで推定した CEFR レベル
+    pub vocab_estimate: Option<i32>, // 推定語彙数
+    pub level_assessed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// CEFR のレベル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CefrLevel {
+    A1,
+    A2,
+    B1,
+    B2,
+    C1,
+    C2,
+}
+
+impl CefrLevel {
+    pub const ALL: [CefrLevel; 6] = [
+        CefrLevel::A1,
+        CefrLevel::A2,
+        CefrLevel::B1,
+        CefrLevel::B2,
+        CefrLevel::C1,
+        CefrLevel::C2,
+    ];
+
+    /// "b2" のような小文字や前後の空白も受け付ける
+    pub fn parse(value: &str) -> Option<CefrLevel> {
+        let value = value.trim();
+        CefrLevel::ALL
+            .into_iter()
+            .find(|level| level.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// 1つ上のレベル (C2 はそのまま)
+    pub fn next(&self) -> CefrLevel {
+        CefrLevel::ALL[(*self as usize + 1).min(CefrLevel::ALL.len() - 1)]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CefrLevel::A1 => "A1",
+            CefrLevel::A2 => "A2",
+            CefrLevel::B1 => "B1",
+            CefrLevel::B2 => "B2",
+            CefrLevel::C1 => "C1",
+            CefrLevel::C2 => "C2",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateUserRequest {
     pub email: String,
@@ -30,6 +79,8 @@ pub struct UserResponse {
     pub email: String,
     pub name: Option<String>,
     pub image: Option<String>,
+    pub cefr_level: Option<String>,
+    pub vocab_estimate: Option<i32>,
 }
 
 impl From<User> for UserResponse {
@@ -39,6 +90,8 @@ impl From<User> for UserResponse {
             email: user.email,
             name: user.name,
             image: user.image,
+            cefr_level: user.cefr_level,
+            vocab_estimate: user.vocab_estimate,
         }
     }
 }
@@ -112,4 +165,23 @@ impl User {
 
         Ok(())
     }
+
+    /// レベル判定テストの結果をプロフィールに保存する
+    pub async fn update_level(
+        pool: &PgPool,
+        id: Uuid,
+        level: CefrLevel,
+        vocab_estimate: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE users SET cefr_level = $1, vocab_estimate = $2, level_assessed_at = NOW() WHERE id = $3",
+        )
+        .bind(level.as_str())
+        .bind(vocab_estimate)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
 }
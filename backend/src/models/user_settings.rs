@@ -0,0 +1,53 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserSettings {
+    pub user_id: Uuid,
+    pub daily_new_words_goal: i32,     // 1日の新規単語目標
+    pub daily_reviews_goal: i32,       // 1日の復習目標
+    pub time_zone: String,             // IANAタイムゾーン名
+    pub day_rollover_hour: i32,        // 日付が切り替わる時刻 (0-23)
+    pub streak_freezes_per_month: i32, // 月あたりのストリークフリーズ回数
+    pub leitner_intervals: sqlx::types::Json<Vec<i32>>, // ライトナーの箱ごとの復習間隔 (日)
+    pub leech_threshold: i32,          // リーチとみなす忘却回数
+    pub leech_auto_suspend: bool,      // リーチを自動で一時停止するか
+    pub max_new_words_per_day: i32,    // 学習プランに入れる新規単語の上限
+    pub max_reviews_per_day: i32,      // 学習プランに入れる復習の上限
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserSettings {
+    /// 設定を取得する。未作成ならデフォルト値で作成する
+    pub async fn get_or_create(pool: &PgPool, user_id: Uuid) -> Result<UserSettings, sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO user_settings (user_id) VALUES ($1) ON CONFLICT (user_id) DO NOTHING",
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        let settings =
+            sqlx::query_as::<_, UserSettings>("SELECT * FROM user_settings WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(settings)
+    }
+
+    /// ユーザーのタイムゾーンと日付切り替え時刻での「今日」
+    pub async fn local_today(&self, pool: &PgPool) -> Result<NaiveDate, sqlx::Error> {
+        let (today,): (NaiveDate,) =
+            sqlx::query_as("SELECT ((NOW() AT TIME ZONE $1) - make_interval(hours => $2))::date")
+                .bind(&self.time_zone)
+                .bind(self.day_rollover_hour)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(today)
+    }
+}
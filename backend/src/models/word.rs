@@ -1,8 +1,96 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
+/// SELECT / RETURNING で使う words テーブルのカラム一覧
+pub const WORD_COLUMNS: &str =
+    "id, word, meaning, translation, part_of_speech, phonetic, example, examples, category, synonyms, \
+     status, due_at, interval_days, repetitions, lapses, leitner_box, last_reviewed_at, \
+     is_leech, mnemonic, etymology_hint, contrasting_example, source, source_metadata, \
+     user_id, created_at, updated_at";
+
+/// 単語の学習ステータス
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordStatus {
+    New,       // 未学習
+    Learning,  // 学習中
+    Reviewing, // 定着に向けて復習中
+    Mastered,  // 習得済み
+    Suspended, // 一時停止 (出題しない)
+    Ignored,   // 既知・学習対象外 (出題しない)
+}
+
+impl WordStatus {
+    pub const ALL: [WordStatus; 6] = [
+        WordStatus::New,
+        WordStatus::Learning,
+        WordStatus::Reviewing,
+        WordStatus::Mastered,
+        WordStatus::Suspended,
+        WordStatus::Ignored,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WordStatus::New => "new",
+            WordStatus::Learning => "learning",
+            WordStatus::Reviewing => "reviewing",
+            WordStatus::Mastered => "mastered",
+            WordStatus::Suspended => "suspended",
+            WordStatus::Ignored => "ignored",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    /// 復習結果では変わらない、手動で設定されたステータスか
+    pub fn is_manual(&self) -> bool {
+        matches!(self, WordStatus::Suspended | WordStatus::Ignored)
+    }
+
+    /// "learning,reviewing" のようなカンマ区切りのクエリ値を解釈する
+    pub fn parse_list(value: &str) -> Result<Vec<WordStatus>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| WordStatus::parse(s).ok_or_else(|| format!("Unknown status: {}", s)))
+            .collect()
+    }
+}
+
+/// 単語の登録元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordSource {
+    Manual,       // 手入力
+    AiSuggestion, // AI の提案から登録
+}
+
+impl WordSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WordSource::Manual => "manual",
+            WordSource::AiSuggestion => "ai_suggestion",
+        }
+    }
+}
+
+/// AI の提案から登録した単語の、提案されたときの情報
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WordSourceMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty_level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance_reason: Option<String>,
+    /// 提案を含む会話分析の ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis_id: Option<Uuid>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Word {
     pub id: String,                                     // 単語識別ID
@@ -12,8 +100,125 @@ pub struct Word {
     pub part_of_speech: sqlx::types::Json<Vec<String>>, // 品詞 (JSON配列)
     pub phonetic: Option<String>,                       // 発音記号
     pub example: Option<String>,                        // 例文
+    pub examples: sqlx::types::Json<Vec<String>>,       // 追加の例文 (JSON配列)
     pub category: Option<String>,                       // カテゴリ
+    pub synonyms: sqlx::types::Json<Vec<String>>,       // 類義語 (JSON配列)
+    pub status: String,                                 // 学習ステータス (WordStatus)
+    pub due_at: Option<DateTime<Utc>>,                  // 次回復習日時 (未学習ならNULL)
+    pub interval_days: i32,                             // 現在の復習間隔 (日)
+    pub repetitions: i32,                               // 連続正解回数
+    pub lapses: i32,                                    // 忘却回数
+    pub leitner_box: i32,                               // ライトナーの箱番号 (1始まり)
+    pub last_reviewed_at: Option<DateTime<Utc>>,        // 最終復習日時
+    pub is_leech: bool,                                 // 何度も忘れる単語 (リーチ) か
+    pub mnemonic: Option<String>,                       // 覚え方 (AI生成)
+    pub etymology_hint: Option<String>,                 // 語源のヒント (AI生成)
+    pub contrasting_example: Option<String>,            // 紛らわしい語との対比例文 (AI生成)
+    pub source: String,                                 // 登録元 (WordSource)
+    pub source_metadata: Option<serde_json::Value>,     // 登録元の情報 (WordSourceMetadata)
     pub user_id: Uuid,                                  // ユーザーID (外部キー)
     pub created_at: DateTime<Utc>,                      // 作成日時
     pub updated_at: DateTime<Utc>,                      // 更新日時
 }
+
+impl Word {
+    /// 登録済みの例文 (example と examples)。空の文は除く
+    pub fn example_sentences(&self) -> Vec<&str> {
+        self.example
+            .iter()
+            .chain(self.examples.iter())
+            .map(|sentence| sentence.trim())
+            .filter(|sentence| !sentence.is_empty())
+            .collect()
+    }
+
+    pub async fn find_for_user(
+        pool: &PgPool,
+        id: &str,
+        user_id: Uuid,
+    ) -> Result<Option<Word>, sqlx::Error> {
+        let sql = format!(
+            "SELECT {} FROM words WHERE id = $1 AND user_id = $2",
+            WORD_COLUMNS
+        );
+        let word = sqlx::query_as::<_, Word>(&sql)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(word)
+    }
+
+    /// 学習中の他の単語 (次回復習日が近い順)。例文に織り込んで一緒に復習させる
+    pub async fn study_headwords(
+        pool: &PgPool,
+        user_id: Uuid,
+        exclude_id: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT word FROM words
+             WHERE user_id = $1 AND id <> $2 AND status IN ('learning', 'reviewing')
+             ORDER BY due_at ASC NULLS LAST, word
+             LIMIT $3",
+        )
+        .bind(user_id)
+        .bind(exclude_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+    /// 復習日が来ている単語 (期限の古い順)
+    pub async fn due_headwords(
+        pool: &PgPool,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT word FROM words
+             WHERE user_id = $1 AND due_at <= NOW() AND status IN ('learning', 'reviewing')
+             ORDER BY due_at, word
+             LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+impl Word {
+    /// テスト用の未学習の単語 (意味は "meaning of <単語>"、その他の項目は空)
+    pub fn for_test(id: &str, word: &str, part_of_speech: &str) -> Word {
+        Word {
+            id: id.to_string(),
+            word: word.to_string(),
+            meaning: format!("meaning of {}", word),
+            translation: None,
+            part_of_speech: sqlx::types::Json(vec![part_of_speech.to_string()]),
+            phonetic: None,
+            example: None,
+            examples: sqlx::types::Json(Vec::new()),
+            category: None,
+            synonyms: sqlx::types::Json(Vec::new()),
+            status: WordStatus::New.as_str().to_string(),
+            due_at: None,
+            interval_days: 0,
+            repetitions: 0,
+            lapses: 0,
+            leitner_box: 1,
+            last_reviewed_at: None,
+            is_leech: false,
+            mnemonic: None,
+            etymology_hint: None,
+            contrasting_example: None,
+            source: WordSource::Manual.as_str().to_string(),
+            source_metadata: None,
+            user_id: Uuid::nil(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::llm::prompt::{Prompt, PromptTemplate};
+use crate::llm::{generate_structured, AiError, AiTask, StructuredOutput, TokenUsage};
+use crate::models::AppState;
+
+/// キャッシュの利用結果 (X-AI-Cache ヘッダーで返す)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// リクエストでキャッシュを使わないよう指定された (結果は保存し直す)
+    Bypass,
+    /// キャッシュが無効になっている
+    Disabled,
+}
+
+impl CacheStatus {
+    pub const HEADER: &'static str = "x-ai-cache";
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Bypass => "BYPASS",
+            CacheStatus::Disabled => "DISABLED",
+        }
+    }
+}
+
+/// 入力の表記ゆれをならす (大文字小文字、連続する空白、末尾の句読点)
+pub fn normalize_input(input: &str) -> String {
+    input
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['?', '？', '.', '。', '!', '！'])
+        .trim_end()
+        .to_lowercase()
+}
+
+/// プロバイダ・モデル・プロンプトの版・正規化した入力から作るキャッシュのキー
+///
+/// テンプレートの文面を変えたら版を上げるので、古いキャッシュは使われなくなる。
+pub fn cache_key(
+    provider: &str,
+    model: &str,
+    template: &PromptTemplate,
+    inputs: &[String],
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        provider,
+        model,
+        template.name,
+        &template.version.to_string(),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    for input in inputs {
+        hasher.update(normalize_input(input).as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// 期限内のキャッシュを読む
+pub async fn lookup<T: StructuredOutput>(
+    pool: &PgPool,
+    key: &str,
+) -> Result<Option<T>, sqlx::Error> {
+    let cached: Option<serde_json::Value> = sqlx::query_scalar(
+        "SELECT response FROM ai_cache WHERE cache_key = $1 AND expires_at > NOW()",
+    )
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    // 型が変わって読めなくなった古いエントリは無かったことにする
+    Ok(cached.and_then(|value| serde_json::from_value(value).ok()))
+}
+
+/// 応答をキャッシュに保存する (同じキーがあれば置き換える)
+pub async fn store<T: Serialize>(
+    pool: &PgPool,
+    key: &str,
+    task: AiTask,
+    output: &T,
+    ttl: std::time::Duration,
+) -> Result<(), sqlx::Error> {
+    let response = serde_json::to_value(output).unwrap_or_default();
+    sqlx::query(
+        "INSERT INTO ai_cache (cache_key, task, response, expires_at)
+         VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))
+         ON CONFLICT (cache_key) DO UPDATE
+         SET response = EXCLUDED.response, created_at = NOW(), expires_at = EXCLUDED.expires_at",
+    )
+    .bind(key)
+    .bind(task.as_str())
+    .bind(response)
+    .bind(ttl.as_secs() as f64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// このアプリの設定でのキャッシュのキー (キャッシュが無効なら None)
+pub fn key_for(app_state: &AppState, task: AiTask, prompt: &Prompt) -> Option<String> {
+    if app_state.ai_cache_ttl.is_zero() {
+        return None;
+    }
+    Some(cache_key(
+        app_state.llm.name(),
+        &app_state.llm.model(task),
+        prompt.template,
+        &prompt.inputs,
+    ))
+}
+
+/// 期限切れのエントリを削除する
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM ai_cache WHERE expires_at <= NOW()")
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// キャッシュがあればそれを返し、無ければ生成して保存する
+///
+/// キャッシュの読み書きに失敗しても AI の応答はそのまま返す。使用量はキャッシュを使ったときは 0。
+pub async fn generate_cached<T: StructuredOutput + Serialize>(
+    app_state: &AppState,
+    task: AiTask,
+    prompt: &Prompt,
+    bypass: bool,
+) -> Result<(T, CacheStatus, TokenUsage), AiError> {
+    let Some(key) = key_for(app_state, task, prompt) else {
+        let (output, usage) =
+            generate_structured(app_state.llm.as_ref(), task, &prompt.text).await?;
+        return Ok((output, CacheStatus::Disabled, usage));
+    };
+    if !bypass {
+        match lookup::<T>(&app_state.pool, &key).await {
+            Ok(Some(output)) => return Ok((output, CacheStatus::Hit, TokenUsage::default())),
+            Ok(None) => {}
+            Err(e) => println!("Failed to read AI cache: {}", e),
+        }
+    }
+
+    let (output, usage): (T, _) =
+        generate_structured(app_state.llm.as_ref(), task, &prompt.text).await?;
+    if let Err(e) = store(&app_state.pool, &key, task, &output, app_state.ai_cache_ttl).await {
+        println!("Failed to write AI cache: {}", e);
+    }
+
+    let status = if bypass {
+        CacheStatus::Bypass
+    } else {
+        CacheStatus::Miss
+    };
+    Ok((output, status, usage))
+}
@@ -0,0 +1,267 @@
+use std::convert::Infallible;
+use std::future::Future;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use shuttle_axum::axum::response::{
+    sse::{Event, KeepAlive, Sse},
+    IntoResponse,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::llm::prompt::{Prompt, PromptTemplate};
+use crate::llm::structured::{finish_structured, StringFieldStream};
+use crate::llm::{AiError, AiTask, StructuredOutput, TokenUsage};
+use crate::models::AppState;
+use crate::services::ai_cache::{self, CacheStatus};
+use crate::services::ai_usage::{self, AiEndpoint};
+
+// クライアントへ送るイベントを溜めておける数
+const EVENT_BUFFER: usize = 32;
+
+type EventSender = mpsc::Sender<Result<Event, Infallible>>;
+
+/// ストリーミングで生成する内容
+pub struct StreamRequest {
+    pub task: AiTask,
+    pub endpoint: AiEndpoint,
+    pub prompt: Prompt,
+    /// 生成途中に少しずつ送る文字列フィールド (例: explanation)
+    pub text_field: &'static str,
+    pub bypass_cache: bool,
+}
+
+/// 構造化出力を Server-Sent Events で返す
+///
+/// `delta` イベントで `text_field` の本文を届いた分から送り、最後に `result` イベントで
+/// 通常のエンドポイントと同じ JSON を送る。失敗したときは `error` イベントを送る。
+/// クライアントが切断したら生成を中断する。`finish` は結果を送る直前に (キャッシュへの保存の後で) 呼ばれる。
+pub async fn stream_structured<T, F, Fut>(
+    app_state: AppState,
+    user_id: Uuid,
+    request: StreamRequest,
+    finish: F,
+) -> impl IntoResponse
+where
+    T: StructuredOutput + Serialize + Send + Sync + 'static,
+    F: FnOnce(T) -> Fut + Send + 'static,
+    Fut: Future<Output = T> + Send,
+{
+    let key = ai_cache::key_for(&app_state, request.task, &request.prompt);
+
+    let cached = match (&key, request.bypass_cache) {
+        (Some(key), false) => match ai_cache::lookup::<T>(&app_state.pool, key).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                println!("Failed to read AI cache: {}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+    let cache_status = match (&key, &cached, request.bypass_cache) {
+        (None, _, _) => CacheStatus::Disabled,
+        (Some(_), Some(_), _) => CacheStatus::Hit,
+        (Some(_), None, true) => CacheStatus::Bypass,
+        (Some(_), None, false) => CacheStatus::Miss,
+    };
+
+    let prompt_id = request.prompt.template.id();
+    let (events, receiver) = mpsc::channel(EVENT_BUFFER);
+    tokio::spawn(async move {
+        match cached {
+            Some(output) => {
+                let output = finish(output).await;
+                send_cached(&events, request.text_field, &output).await;
+                ai_usage::record(
+                    &app_state,
+                    user_id,
+                    request.endpoint,
+                    request.prompt.template,
+                    TokenUsage::default(),
+                    true,
+                )
+                .await;
+            }
+            None => generate(&app_state, user_id, &request, key, &events, finish).await,
+        }
+    });
+
+    (
+        [
+            (CacheStatus::HEADER, cache_status.as_str().to_string()),
+            (PromptTemplate::HEADER, prompt_id),
+        ],
+        Sse::new(ReceiverStream::new(receiver)).keep_alive(KeepAlive::default()),
+    )
+}
+
+async fn generate<T, F, Fut>(
+    app_state: &AppState,
+    user_id: Uuid,
+    request: &StreamRequest,
+    key: Option<String>,
+    events: &EventSender,
+    finish: F,
+) where
+    T: StructuredOutput + Serialize,
+    F: FnOnce(T) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let (delta_sender, mut deltas) = mpsc::unbounded_channel();
+    let mut field = StringFieldStream::new(request.text_field);
+    let schema = T::schema();
+    // 途中で失敗・中断したときの使用量の概算に使う
+    let mut streamed = String::new();
+
+    // 生成中は届いた断片を送り続け、クライアントが切断したら Future ごと破棄して中断する
+    let completion = {
+        let generation = app_state.llm.generate_stream(
+            request.task,
+            &request.prompt.text,
+            &schema,
+            delta_sender,
+        );
+        tokio::pin!(generation);
+        loop {
+            tokio::select! {
+                result = &mut generation => break Some(result),
+                Some(delta) = deltas.recv() => {
+                    streamed.push_str(&delta);
+                    if !send_delta(events, field.push(&delta)).await {
+                        break None;
+                    }
+                }
+                _ = events.closed() => break None,
+            }
+        }
+    };
+    let completion = match completion {
+        Some(Ok(completion)) => completion,
+        Some(Err(err)) => {
+            while let Ok(delta) = deltas.try_recv() {
+                streamed.push_str(&delta);
+            }
+            record_unfinished(app_state, user_id, request, &streamed).await;
+            return send_error(events, err.into()).await;
+        }
+        None => return record_unfinished(app_state, user_id, request, &streamed).await,
+    };
+    let generated = completion.usage;
+    while let Ok(delta) = deltas.try_recv() {
+        if !send_delta(events, field.push(&delta)).await {
+            return record_usage(app_state, user_id, request, generated).await;
+        }
+    }
+
+    // 形式が崩れていれば作り直させる (その場合の本文は result イベントで届く)
+    let finished = tokio::select! {
+        result = finish_structured::<T>(app_state.llm.as_ref(), request.task, &request.prompt.text, completion) => result,
+        _ = events.closed() => return record_usage(app_state, user_id, request, generated).await,
+    };
+    let (output, usage) = match finished {
+        Ok(finished) => finished,
+        Err(err) => {
+            record_usage(app_state, user_id, request, err.usage()).await;
+            return send_error(events, err).await;
+        }
+    };
+
+    ai_usage::record(
+        app_state,
+        user_id,
+        request.endpoint,
+        request.prompt.template,
+        usage,
+        false,
+    )
+    .await;
+    if let Some(key) = key {
+        if let Err(e) = ai_cache::store(
+            &app_state.pool,
+            &key,
+            request.task,
+            &output,
+            app_state.ai_cache_ttl,
+        )
+        .await
+        {
+            println!("Failed to write AI cache: {}", e);
+        }
+    }
+    let output = finish(output).await;
+    send_result(events, &output).await;
+}
+
+/// 生成し終わらなかった呼び出しの使用量を、届いた分から概算して記録する
+async fn record_unfinished(
+    app_state: &AppState,
+    user_id: Uuid,
+    request: &StreamRequest,
+    streamed: &str,
+) {
+    if !streamed.is_empty() {
+        let usage = TokenUsage::estimate(&request.prompt.text, streamed);
+        record_usage(app_state, user_id, request, usage).await;
+    }
+}
+
+/// 失敗・中断した呼び出しで使ったトークンを記録する (使っていなければ何もしない)
+async fn record_usage(
+    app_state: &AppState,
+    user_id: Uuid,
+    request: &StreamRequest,
+    usage: TokenUsage,
+) {
+    if usage != TokenUsage::default() {
+        ai_usage::record(
+            app_state,
+            user_id,
+            request.endpoint,
+            request.prompt.template,
+            usage,
+            false,
+        )
+        .await;
+    }
+}
+
+/// キャッシュの内容を、本文をまとめて1回送ってから結果を送る
+async fn send_cached<T: Serialize>(events: &EventSender, text_field: &str, output: &T) {
+    let value = serde_json::to_value(output).unwrap_or_default();
+    let text = value
+        .get(text_field)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    if send_delta(events, text).await {
+        send_result(events, output).await;
+    }
+}
+
+/// 本文の断片を送る。クライアントが切断していれば false
+async fn send_delta(events: &EventSender, text: String) -> bool {
+    if text.is_empty() {
+        return !events.is_closed();
+    }
+    let event = Event::default()
+        .event("delta")
+        .data(json!({ "text": text }).to_string());
+    events.send(Ok(event)).await.is_ok()
+}
+
+async fn send_result<T: Serialize>(events: &EventSender, output: &T) {
+    let data = serde_json::to_string(output).unwrap_or_default();
+    let _ = events
+        .send(Ok(Event::default().event("result").data(data)))
+        .await;
+}
+
+async fn send_error(events: &EventSender, err: AiError) {
+    let data = json!({ "error": err.code(), "message": err.to_string() }).to_string();
+    let _ = events
+        .send(Ok(Event::default().event("error").data(data)))
+        .await;
+}
@@ -0,0 +1,223 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::llm::prompt::PromptTemplate;
+use crate::llm::{AiError, AiQuota, TokenUsage};
+use crate::models::AppState;
+
+/// 使用量を記録する AI のエンドポイント
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiEndpoint {
+    ConversationAnalysis,
+    VocabularyHelp,
+    WordSuggestions,
+    SentenceCheck,
+    AnswerGrading,
+    WordAutofill,
+    ExampleSentences,
+    TutorChat,
+    LeechAids,
+    ClozeSentence,
+}
+
+impl AiEndpoint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiEndpoint::ConversationAnalysis => "conversation_analysis",
+            AiEndpoint::VocabularyHelp => "vocabulary_help",
+            AiEndpoint::WordSuggestions => "word_suggestions",
+            AiEndpoint::SentenceCheck => "sentence_check",
+            AiEndpoint::AnswerGrading => "answer_grading",
+            AiEndpoint::WordAutofill => "word_autofill",
+            AiEndpoint::ExampleSentences => "example_sentences",
+            AiEndpoint::TutorChat => "tutor_chat",
+            AiEndpoint::LeechAids => "leech_aids",
+            AiEndpoint::ClozeSentence => "cloze_sentence",
+        }
+    }
+}
+
+/// 期間 (UTC の1日・1か月) ごとの使用量
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodUsage {
+    pub used_tokens: u64,
+    /// None なら無制限
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl PeriodUsage {
+    fn new(used_tokens: i64, limit: Option<u64>, resets_at: DateTime<Utc>) -> Self {
+        let used_tokens = used_tokens.max(0) as u64;
+        PeriodUsage {
+            used_tokens,
+            limit,
+            remaining: limit.map(|limit| limit.saturating_sub(used_tokens)),
+            resets_at,
+        }
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EndpointUsage {
+    pub endpoint: String,
+    pub requests: i64,
+    /// キャッシュから返したリクエスト数 (トークンは使わない)
+    pub cached_requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSummary {
+    pub daily: PeriodUsage,
+    pub monthly: PeriodUsage,
+    /// 今月のエンドポイント別の内訳
+    pub endpoints: Vec<EndpointUsage>,
+}
+
+#[derive(FromRow)]
+struct PeriodTotals {
+    daily_tokens: i64,
+    monthly_tokens: i64,
+    day_resets_at: DateTime<Utc>,
+    month_resets_at: DateTime<Utc>,
+}
+
+async fn period_usage(
+    pool: &PgPool,
+    user_id: Uuid,
+    quota: AiQuota,
+) -> Result<(PeriodUsage, PeriodUsage), sqlx::Error> {
+    let totals = sqlx::query_as::<_, PeriodTotals>(
+        "WITH bounds AS (
+             SELECT date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS day_start,
+                    date_trunc('month', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS month_start
+         )
+         SELECT
+             COALESCE(SUM(u.input_tokens + u.output_tokens) FILTER (WHERE u.created_at >= b.day_start), 0)::BIGINT AS daily_tokens,
+             COALESCE(SUM(u.input_tokens + u.output_tokens), 0)::BIGINT AS monthly_tokens,
+             b.day_start + INTERVAL '1 day' AS day_resets_at,
+             b.month_start + INTERVAL '1 month' AS month_resets_at
+         FROM bounds b
+         LEFT JOIN ai_usage u ON u.user_id = $1 AND u.created_at >= b.month_start
+         GROUP BY b.day_start, b.month_start",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((
+        PeriodUsage::new(
+            totals.daily_tokens,
+            quota.daily_tokens,
+            totals.day_resets_at,
+        ),
+        PeriodUsage::new(
+            totals.monthly_tokens,
+            quota.monthly_tokens,
+            totals.month_resets_at,
+        ),
+    ))
+}
+
+/// 上限に達していれば 429 で断る
+pub async fn check_quota(app_state: &AppState, user_id: Uuid) -> Result<(), AiError> {
+    let quota = app_state.ai_quota;
+    if quota == AiQuota::default() {
+        return Ok(());
+    }
+
+    let (daily, monthly) = period_usage(&app_state.pool, user_id, quota)
+        .await
+        .map_err(|e| AiError::Internal(e.to_string()))?;
+    for (period, usage) in [("monthly", monthly), ("daily", daily)] {
+        if let (true, Some(limit)) = (usage.is_exhausted(), usage.limit) {
+            return Err(AiError::QuotaExceeded {
+                period,
+                limit,
+                used: usage.used_tokens,
+                resets_at: usage.resets_at,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 1回の呼び出しの使用量を、使ったプロンプトの版と一緒に記録する (失敗しても応答は返す)
+pub async fn record(
+    app_state: &AppState,
+    user_id: Uuid,
+    endpoint: AiEndpoint,
+    prompt: &PromptTemplate,
+    usage: TokenUsage,
+    cached: bool,
+) {
+    let result = sqlx::query(
+        "INSERT INTO ai_usage (user_id, endpoint, prompt_version, input_tokens, output_tokens, cached)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(user_id)
+    .bind(endpoint.as_str())
+    .bind(prompt.id())
+    .bind(usage.input_tokens as i32)
+    .bind(usage.output_tokens as i32)
+    .bind(cached)
+    .execute(&app_state.pool)
+    .await;
+
+    if let Err(e) = result {
+        println!("Failed to record AI usage: {}", e);
+    }
+}
+
+/// 生成に失敗した場合も、使えない出力などで使ったトークンがあれば記録してから結果を返す
+pub async fn record_on_error<T>(
+    app_state: &AppState,
+    user_id: Uuid,
+    endpoint: AiEndpoint,
+    prompt: &PromptTemplate,
+    result: Result<T, AiError>,
+) -> Result<T, AiError> {
+    if let Err(err) = &result {
+        let usage = err.usage();
+        if usage != TokenUsage::default() {
+            record(app_state, user_id, endpoint, prompt, usage, false).await;
+        }
+    }
+    result
+}
+
+/// 今日・今月の使用量と上限、今月のエンドポイント別の内訳
+pub async fn summary(app_state: &AppState, user_id: Uuid) -> Result<UsageSummary, sqlx::Error> {
+    let (daily, monthly) = period_usage(&app_state.pool, user_id, app_state.ai_quota).await?;
+
+    let endpoints = sqlx::query_as::<_, EndpointUsage>(
+        "SELECT endpoint,
+                COUNT(*) AS requests,
+                COUNT(*) FILTER (WHERE cached) AS cached_requests,
+                COALESCE(SUM(input_tokens), 0)::BIGINT AS input_tokens,
+                COALESCE(SUM(output_tokens), 0)::BIGINT AS output_tokens
+         FROM ai_usage
+         WHERE user_id = $1
+           AND created_at >= date_trunc('month', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+         GROUP BY endpoint
+         ORDER BY endpoint",
+    )
+    .bind(user_id)
+    .fetch_all(&app_state.pool)
+    .await?;
+
+    Ok(UsageSummary {
+        daily,
+        monthly,
+        endpoints,
+    })
+}
@@ -0,0 +1,231 @@
+use crate::services::inflection::word_forms;
+
+/// 空欄として表示する文字列
+pub const BLANK: &str = "_____";
+
+// 句動詞の構成語の間に挟まってよい語数 ("give it back to" など)
+const MAX_GAP: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cloze {
+    /// 空欄入りの文
+    pub text: String,
+    /// 空欄に入る語 (文中の形のまま、空欄の順)
+    pub answers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClozeCheck {
+    pub correct: bool,
+    /// 語は合っているが変化形が違う (例: "ran" が正解のところに "run")
+    pub wrong_form: bool,
+}
+
+struct Token<'a> {
+    start: usize,
+    end: usize,
+    text: &'a str,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\'' || c == '’' || c == '-'
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (is_word_char(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push(Token {
+                    start: s,
+                    end: i,
+                    text: &text[s..i],
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token {
+            start: s,
+            end: text.len(),
+            text: &text[s..],
+        });
+    }
+    tokens
+}
+
+/// 例文を文単位に分ける
+fn sentences(text: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if matches!(c, '.' | '!' | '?' | '。' | '\n') {
+            let end = i + c.len_utf8();
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                result.push(sentence);
+            }
+            start = end;
+        }
+    }
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        result.push(rest);
+    }
+    result
+}
+
+/// 見出し語 (句動詞などの複数語も可) の各構成語の位置を文中から探す
+///
+/// 各構成語はどの変化形でもよく、順番通りに、間に最大 `MAX_GAP` 語を挟んで現れる必要がある。
+fn find_phrase(tokens: &[Token<'_>], headword: &str) -> Option<Vec<usize>> {
+    let parts: Vec<Vec<String>> = headword
+        .split_whitespace()
+        .map(|part| word_forms(&part.to_lowercase()))
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+
+    let lowered: Vec<String> = tokens
+        .iter()
+        .map(|t| t.text.to_lowercase().replace('’', "'"))
+        .collect();
+
+    for first in 0..tokens.len() {
+        if !parts[0].contains(&lowered[first]) {
+            continue;
+        }
+
+        let mut positions = vec![first];
+        for forms in &parts[1..] {
+            let from = positions.last().unwrap() + 1;
+            let to = (from + MAX_GAP + 1).min(tokens.len());
+            match (from..to).find(|&i| forms.contains(&lowered[i])) {
+                Some(i) => positions.push(i),
+                None => break,
+            }
+        }
+
+        if positions.len() == parts.len() {
+            return Some(positions);
+        }
+    }
+
+    None
+}
+
+/// 例文から見出し語を空欄にした問題を作る。見つからなければ None
+pub fn make_cloze(example: &str, headword: &str) -> Option<Cloze> {
+    for sentence in sentences(example) {
+        let tokens = tokenize(sentence);
+        let Some(positions) = find_phrase(&tokens, headword) else {
+            continue;
+        };
+
+        let mut text = String::new();
+        let mut answers = Vec::new();
+        let mut cursor = 0;
+        for &i in &positions {
+            text.push_str(&sentence[cursor..tokens[i].start]);
+            text.push_str(BLANK);
+            answers.push(tokens[i].text.to_string());
+            cursor = tokens[i].end;
+        }
+        text.push_str(&sentence[cursor..]);
+
+        return Some(Cloze { text, answers });
+    }
+
+    None
+}
+
+/// 回答を採点する。空欄が複数ある場合は空白区切りや "..." 区切りで順に答える
+pub fn check_answer(cloze: &Cloze, headword: &str, answer: &str) -> ClozeCheck {
+    let given: Vec<String> = tokenize(answer)
+        .iter()
+        .map(|t| t.text.to_lowercase().replace('’', "'"))
+        .collect();
+    let expected: Vec<String> = cloze
+        .answers
+        .iter()
+        .map(|a| a.to_lowercase().replace('’', "'"))
+        .collect();
+
+    if given == expected {
+        return ClozeCheck {
+            correct: true,
+            wrong_form: false,
+        };
+    }
+
+    // 各構成語が見出し語の別の変化形になっているか
+    let parts: Vec<&str> = headword.split_whitespace().collect();
+    let wrong_form = given.len() == parts.len()
+        && given
+            .iter()
+            .zip(parts.iter())
+            .all(|(g, part)| word_forms(part).contains(g));
+
+    ClozeCheck {
+        correct: false,
+        wrong_form,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_an_inflected_form() {
+        let cloze = make_cloze("She studied all night. Then she slept.", "study").unwrap();
+        assert_eq!(cloze.text, format!("She {} all night.", BLANK));
+        assert_eq!(cloze.answers, vec!["studied"]);
+    }
+
+    #[test]
+    fn blanks_each_part_of_a_separated_phrasal_verb() {
+        let cloze = make_cloze("Please give it back tomorrow.", "give back").unwrap();
+        assert_eq!(
+            cloze.text,
+            format!("Please {} it {} tomorrow.", BLANK, BLANK)
+        );
+        assert_eq!(cloze.answers, vec!["give", "back"]);
+    }
+
+    #[test]
+    fn returns_none_when_the_word_is_missing() {
+        assert_eq!(make_cloze("Nothing to see here.", "study"), None);
+    }
+
+    #[test]
+    fn requires_the_form_used_in_the_sentence() {
+        let cloze = make_cloze("He ran to the station.", "run").unwrap();
+        assert_eq!(
+            check_answer(&cloze, "run", "Ran"),
+            ClozeCheck {
+                correct: true,
+                wrong_form: false
+            }
+        );
+        assert_eq!(
+            check_answer(&cloze, "run", "run"),
+            ClozeCheck {
+                correct: false,
+                wrong_form: true
+            }
+        );
+        assert_eq!(
+            check_answer(&cloze, "run", "walked"),
+            ClozeCheck {
+                correct: false,
+                wrong_form: false
+            }
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::models::word::Word;
+use crate::services::cloze;
+use crate::services::inflection;
+
+/// 1問あたりの選択肢の数
+pub const CHOICE_COUNT: usize = 4;
+
+/// 問題の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionKind {
+    /// 例文の空欄に入る語を選ぶ (TOEIC Part 5 形式)
+    SentenceCompletion,
+    /// 見出し語の意味を選ぶ
+    Meaning,
+}
+
+impl QuestionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionKind::SentenceCompletion => "sentence_completion",
+            QuestionKind::Meaning => "meaning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedQuestion {
+    pub word_id: String,
+    pub kind: QuestionKind,
+    pub prompt: String,
+    pub choices: Vec<String>,
+    pub correct_index: usize,
+}
+
+/// 誤答の選択肢を選ぶ
+///
+/// 同じ品詞の単語を優先し、正解や他の選択肢と重複するもの、`label` が None の語は除く。
+fn pick_distractors<R: Rng>(
+    word: &Word,
+    pool: &[Word],
+    correct: &str,
+    label: impl Fn(&Word) -> Option<String>,
+    rng: &mut R,
+) -> Option<Vec<String>> {
+    let mut candidates: Vec<&Word> = pool.iter().filter(|w| w.id != word.id).collect();
+    candidates.shuffle(rng);
+    candidates.sort_by_key(|w| {
+        let shares_pos = w
+            .part_of_speech
+            .iter()
+            .any(|pos| word.part_of_speech.contains(pos));
+        !shares_pos
+    });
+
+    let mut seen = vec![correct.trim().to_lowercase()];
+    let mut distractors = Vec::new();
+    for candidate in candidates {
+        let Some(text) = label(candidate) else {
+            continue;
+        };
+        let key = text.trim().to_lowercase();
+        if key.is_empty() || seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        distractors.push(text);
+        if distractors.len() == CHOICE_COUNT - 1 {
+            return Some(distractors);
+        }
+    }
+
+    None
+}
+
+/// 文頭の空欄なら選択肢も大文字で始める
+fn match_case(answer: &str, text: String) -> String {
+    if !answer.starts_with(char::is_uppercase) {
+        return text;
+    }
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
+
+/// 1語分の問題を作る。例文 (追加の例文を含む) があれば空欄補充、無ければ意味を問う
+///
+/// 誤答の選択肢が足りない場合は None。
+pub fn build_question<R: Rng>(
+    word: &Word,
+    pool: &[Word],
+    rng: &mut R,
+) -> Option<GeneratedQuestion> {
+    // 句動詞などで空欄が複数になる文や、不規則変化で誤答の語形をそろえられない文は
+    // 選択肢にしにくいので意味の問題にする
+    let mut examples = word.example_sentences();
+    examples.shuffle(rng);
+    let sentence = examples.into_iter().find_map(|example| {
+        let c = cloze::make_cloze(example, &word.word).filter(|c| c.answers.len() == 1)?;
+        let form = inflection::form_of(&word.word, &c.answers[0])?;
+        Some((c, form))
+    });
+
+    let (kind, prompt, correct, distractors) = match sentence {
+        Some((c, form)) => {
+            // 誤答も正解と同じ語形にする (正解が "studied" なら "improved" のように)
+            let correct = c.answers[0].clone();
+            let label = |w: &Word| {
+                inflection::inflect_as(&w.word, &w.part_of_speech, form)
+                    .map(|text| match_case(&correct, text))
+            };
+            let distractors = pick_distractors(word, pool, &correct, label, rng)?;
+            (
+                QuestionKind::SentenceCompletion,
+                c.text,
+                correct,
+                distractors,
+            )
+        }
+        None => {
+            let correct = word.meaning.clone();
+            let distractors =
+                pick_distractors(word, pool, &correct, |w| Some(w.meaning.clone()), rng)?;
+            (
+                QuestionKind::Meaning,
+                word.word.clone(),
+                correct,
+                distractors,
+            )
+        }
+    };
+
+    let mut choices = distractors;
+    let correct_index = rng.gen_range(0..=choices.len());
+    choices.insert(correct_index, correct);
+
+    Some(GeneratedQuestion {
+        word_id: word.id.clone(),
+        kind,
+        prompt,
+        choices,
+        correct_index,
+    })
+}
+
+/// 出題候補からランダムに `count` 問を作る。誤答は `pool` 全体から選ぶ
+pub fn build_exam<R: Rng>(
+    candidates: &[Word],
+    pool: &[Word],
+    count: usize,
+    rng: &mut R,
+) -> Vec<GeneratedQuestion> {
+    let mut order: Vec<&Word> = candidates.iter().collect();
+    order.shuffle(rng);
+
+    order
+        .into_iter()
+        .filter_map(|word| build_question(word, pool, rng))
+        .take(count)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use sqlx::types::Json;
+
+    fn word(id: &str, headword: &str, part_of_speech: &str, example: Option<&str>) -> Word {
+        let mut word = Word::for_test(id, headword, part_of_speech);
+        word.example = example.map(str::to_string);
+        word
+    }
+
+    fn pool() -> Vec<Word> {
+        vec![
+            word("2", "improve", "verb", None),
+            word("3", "carry", "verb", None),
+            word("4", "stop", "verb", None),
+            word("5", "run", "verb", None),
+            word("6", "table", "noun", None),
+        ]
+    }
+
+    #[test]
+    fn sentence_completion_distractors_take_the_answer_form() {
+        let target = word("1", "study", "verb", Some("Studied hard, she passed."));
+        let mut rng = StdRng::seed_from_u64(1);
+        let question = build_question(&target, &pool(), &mut rng).unwrap();
+
+        assert_eq!(question.kind, QuestionKind::SentenceCompletion);
+        assert_eq!(question.choices[question.correct_index], "Studied");
+        let mut choices = question.choices.clone();
+        choices.sort();
+        assert_eq!(choices, ["Carried", "Improved", "Stopped", "Studied"]);
+    }
+
+    #[test]
+    fn sentence_completion_uses_the_additional_examples() {
+        let mut target = word("1", "carry", "verb", None);
+        target.examples = Json(vec!["She carries a heavy bag.".to_string()]);
+        let mut rng = StdRng::seed_from_u64(1);
+        let question = build_question(&target, &pool(), &mut rng).unwrap();
+
+        assert_eq!(question.kind, QuestionKind::SentenceCompletion);
+        assert_eq!(question.prompt, "She _____ a heavy bag.");
+        assert_eq!(question.choices[question.correct_index], "carries");
+    }
+
+    #[test]
+    fn irregular_answers_fall_back_to_a_meaning_question() {
+        let target = word("1", "run", "verb", Some("He ran to the station."));
+        let mut rng = StdRng::seed_from_u64(1);
+        let question = build_question(&target, &pool(), &mut rng).unwrap();
+
+        assert_eq!(question.kind, QuestionKind::Meaning);
+        assert_eq!(question.choices[question.correct_index], "meaning of run");
+    }
+}
@@ -0,0 +1,115 @@
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+use crate::services::scheduler::{self, ReviewAnswer, ReviewState};
+
+/// 予測する1日分の件数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ForecastDay {
+    pub date: NaiveDate,
+    /// その日に期限が来る復習の数 (その日に追加する新規単語の初回学習を含む)
+    pub reviews: u32,
+    /// その日に追加する新規単語の数
+    pub new_words: u32,
+}
+
+/// 今後 `days` 日間の復習数を予測する
+///
+/// `cards` は (今日から何日後に期限が来るか, 現在の状態)。期限切れの単語は今日に数える。
+/// すべての復習に正解し、期限の日に復習するものとしてライトナーの箱で次の期限を進める。
+/// `new_words_per_day` を指定すると、毎日その数の新規単語を追加した場合を加えて予測する。
+pub fn simulate(
+    cards: &[(i64, ReviewState)],
+    today: NaiveDate,
+    days: usize,
+    new_words_per_day: u32,
+    leitner_intervals: &[i32],
+) -> Vec<ForecastDay> {
+    let mut forecast: Vec<ForecastDay> = (0..days)
+        .map(|offset| ForecastDay {
+            date: today + Duration::days(offset as i64),
+            reviews: 0,
+            new_words: 0,
+        })
+        .collect();
+
+    let run = |mut due: usize, mut state: ReviewState, forecast: &mut [ForecastDay]| {
+        while due < days {
+            forecast[due].reviews += 1;
+            state = scheduler::schedule_leitner(
+                state,
+                ReviewAnswer { correct: true },
+                leitner_intervals,
+            );
+            due += state.interval_days.max(1) as usize;
+        }
+    };
+
+    for &(offset, state) in cards {
+        run(offset.max(0) as usize, state, &mut forecast);
+    }
+
+    if new_words_per_day > 0 {
+        for day in 0..days {
+            forecast[day].new_words = new_words_per_day;
+            for _ in 0..new_words_per_day {
+                run(day, ReviewState::default(), &mut forecast);
+            }
+        }
+    }
+
+    forecast
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVALS: &[i32] = &[1, 2, 4];
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 6, 1).unwrap()
+    }
+
+    fn reviews(forecast: &[ForecastDay]) -> Vec<u32> {
+        forecast.iter().map(|day| day.reviews).collect()
+    }
+
+    #[test]
+    fn moves_each_card_through_the_leitner_boxes() {
+        let forecast = simulate(&[(0, ReviewState::default())], today(), 11, 0, INTERVALS);
+
+        // 箱2 (2日後)、箱3 (4日後) と進み、最後の箱にとどまる
+        assert_eq!(reviews(&forecast), [1, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(forecast[0].date, today());
+        assert_eq!(
+            forecast[10].date,
+            NaiveDate::from_ymd_opt(2026, 6, 11).unwrap()
+        );
+        assert!(forecast.iter().all(|day| day.new_words == 0));
+    }
+
+    #[test]
+    fn counts_overdue_cards_today_and_ignores_cards_beyond_the_range() {
+        let last_box = ReviewState {
+            leitner_box: 3,
+            ..ReviewState::default()
+        };
+        let forecast = simulate(&[(-3, last_box), (5, last_box)], today(), 4, 0, INTERVALS);
+        assert_eq!(reviews(&forecast), [1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn adds_the_first_study_and_the_reviews_of_new_words() {
+        let forecast = simulate(&[], today(), 5, 2, INTERVALS);
+
+        assert!(forecast.iter().all(|day| day.new_words == 2));
+        // 毎日の初回学習に、2日前に追加した単語の復習が加わる
+        assert_eq!(reviews(&forecast), [2, 2, 4, 4, 4]);
+    }
+
+    #[test]
+    fn returns_nothing_for_zero_days() {
+        assert!(simulate(&[(0, ReviewState::default())], today(), 0, 3, INTERVALS).is_empty());
+    }
+}
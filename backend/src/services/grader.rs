@@ -0,0 +1,409 @@
+use serde::Serialize;
+
+use crate::models::word::Word;
+
+/// これ以上のスコアは正解とみなす
+pub const CORRECT_THRESHOLD: f64 = 0.85;
+/// これ以下のスコアは不正解とみなす
+pub const INCORRECT_THRESHOLD: f64 = 0.4;
+
+const MIDPOINT: f64 = (CORRECT_THRESHOLD + INCORRECT_THRESHOLD) / 2.0;
+
+// 意味の比較で無視する英語の機能語
+const STOPWORDS: &[&str] = &[
+    "a",
+    "an",
+    "the",
+    "to",
+    "of",
+    "in",
+    "on",
+    "at",
+    "for",
+    "by",
+    "with",
+    "and",
+    "or",
+    "be",
+    "is",
+    "are",
+    "was",
+    "were",
+    "something",
+    "someone",
+    "somebody",
+    "sth",
+    "sb",
+    "that",
+    "which",
+    "who",
+    "it",
+    "its",
+    "as",
+    "from",
+    "into",
+];
+
+// 日本語訳を候補に分割する区切り文字
+const TRANSLATION_SEPARATORS: &[char] = &['、', '，', ',', ';', '；', '/', '／', '・', '\n'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Correct,
+    Incorrect,
+    Ambiguous,
+}
+
+/// 回答がどのフィールドと一致したか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    Synonym,
+    Translation,
+    Meaning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeOutcome {
+    pub verdict: Verdict,
+    /// 0.0〜1.0 の一致度
+    pub score: f64,
+    /// 判定の確からしさ。曖昧帯の中央で 0.0、両端で 1.0
+    pub confidence: f64,
+    pub matched_field: Option<MatchField>,
+    pub matched_text: Option<String>,
+}
+
+impl GradeOutcome {
+    /// 曖昧な判定をAIに回せない場合に使う暫定的な正誤
+    pub fn is_probably_correct(&self) -> bool {
+        match self.verdict {
+            Verdict::Correct => true,
+            Verdict::Incorrect => false,
+            Verdict::Ambiguous => self.score >= MIDPOINT,
+        }
+    }
+}
+
+/// 単語に対する回答をLLMを使わずに採点する
+///
+/// 類義語とは編集距離、日本語訳とは完全/部分一致、
+/// 英語の意味とはトークンの重なりで比較し、最も高いスコアを採用する。
+/// 見出し語は問題文に表示されているので、書き写しただけの回答を正解にしないよう比較しない。
+pub fn grade_answer(word: &Word, answer: &str) -> GradeOutcome {
+    let mut best: (f64, Option<MatchField>, Option<String>) = (0.0, None, None);
+    let mut consider = |score: f64, field: MatchField, text: &str| {
+        if score > best.0 {
+            best = (score, Some(field), Some(text.to_string()));
+        }
+    };
+
+    let normalized_answer = normalize(answer);
+    if normalized_answer.is_empty() || normalized_answer == normalize(&word.word) {
+        return outcome(0.0, None, None);
+    }
+
+    for synonym in word.synonyms.iter() {
+        consider(
+            spelling_similarity(&normalized_answer, &normalize(synonym)),
+            MatchField::Synonym,
+            synonym,
+        );
+    }
+
+    if let Some(translation) = &word.translation {
+        for candidate in split_translation(translation) {
+            consider(
+                translation_similarity(&normalized_answer, &normalize(&candidate)),
+                MatchField::Translation,
+                &candidate,
+            );
+        }
+    }
+
+    consider(
+        token_overlap(&normalized_answer, &normalize(&word.meaning)),
+        MatchField::Meaning,
+        &word.meaning,
+    );
+
+    let (score, field, text) = best;
+    outcome(score, field, text)
+}
+
+fn outcome(score: f64, field: Option<MatchField>, text: Option<String>) -> GradeOutcome {
+    let verdict = if score >= CORRECT_THRESHOLD {
+        Verdict::Correct
+    } else if score <= INCORRECT_THRESHOLD {
+        Verdict::Incorrect
+    } else {
+        Verdict::Ambiguous
+    };
+
+    let confidence = match verdict {
+        Verdict::Correct => 0.5 + 0.5 * (score - CORRECT_THRESHOLD) / (1.0 - CORRECT_THRESHOLD),
+        Verdict::Incorrect => 0.5 + 0.5 * (INCORRECT_THRESHOLD - score) / INCORRECT_THRESHOLD,
+        Verdict::Ambiguous => {
+            let half_width = (CORRECT_THRESHOLD - INCORRECT_THRESHOLD) / 2.0;
+            0.5 * (score - MIDPOINT).abs() / half_width
+        }
+    };
+
+    GradeOutcome {
+        verdict,
+        score: round3(score),
+        confidence: round3(confidence.clamp(0.0, 1.0)),
+        matched_field: field,
+        matched_text: text,
+    }
+}
+
+fn round3(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+/// 大文字小文字・全角英数・記号・余分な空白の違いを吸収する
+pub fn normalize(text: &str) -> String {
+    let mapped: String = text
+        .chars()
+        .map(|c| match c {
+            // 全角ASCIIを半角に変換
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .map(|c| {
+            if c.is_alphanumeric() || c == '\'' || c == '-' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect();
+
+    mapped
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c| c == '\'' || c == '-')
+        .to_string()
+}
+
+/// 動詞の "to " や冠詞を落とした比較用の形
+fn strip_leading_particles(text: &str) -> &str {
+    for prefix in ["to ", "a ", "an ", "the "] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return rest;
+        }
+    }
+    text
+}
+
+fn spelling_similarity(answer: &str, target: &str) -> f64 {
+    let answer = strip_leading_particles(answer);
+    let target = strip_leading_particles(target);
+    if target.is_empty() {
+        return 0.0;
+    }
+    if answer == target {
+        return 1.0;
+    }
+
+    let a: Vec<char> = answer.chars().collect();
+    let b: Vec<char> = target.chars().collect();
+    let distance = edit_distance(&a, &b);
+    let longest = a.len().max(b.len());
+    // 短い単語での1文字違いは別の単語であることが多いので、4文字未満は完全一致のみ
+    if longest < 4 {
+        return 0.0;
+    }
+    1.0 - distance as f64 / longest as f64
+}
+
+/// 隣接文字の入れ替えを1操作として数える編集距離 (Optimal String Alignment)
+pub fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let (n, m) = (a.len(), b.len());
+    let mut d = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[n][m]
+}
+
+/// 「（〜を）走る、運営する」のような訳語を個々の候補に分ける
+fn split_translation(translation: &str) -> Vec<String> {
+    let mut without_notes = String::new();
+    let mut depth = 0;
+    for c in translation.chars() {
+        match c {
+            '(' | '（' | '[' | '［' | '【' => depth += 1,
+            ')' | '）' | ']' | '］' | '】' => depth = (depth - 1).max(0),
+            _ if depth == 0 => without_notes.push(c),
+            _ => {}
+        }
+    }
+
+    let mut candidates: Vec<String> = without_notes
+        .split(TRANSLATION_SEPARATORS)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    // 注記付きの原文そのものも候補に残す
+    candidates.push(translation.trim().to_string());
+    candidates
+}
+
+fn translation_similarity(answer: &str, candidate: &str) -> f64 {
+    let answer: String = answer.chars().filter(|c| !c.is_whitespace()).collect();
+    let candidate: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+    if candidate.is_empty() || answer.is_empty() {
+        return 0.0;
+    }
+    if answer == candidate {
+        return 1.0;
+    }
+
+    let answer_len = answer.chars().count();
+    let candidate_len = candidate.chars().count();
+    let (shorter, longer) = if answer_len <= candidate_len {
+        (answer_len, candidate_len)
+    } else {
+        (candidate_len, answer_len)
+    };
+
+    // 1文字だけの部分一致は偶然のことが多いので数えない
+    if shorter >= 2 && (candidate.contains(&answer) || answer.contains(&candidate)) {
+        // 「走る」と「走らせる」のような部分一致は長さの比で評価する
+        return 0.55 + 0.4 * shorter as f64 / longer as f64;
+    }
+
+    let a: Vec<char> = answer.chars().collect();
+    let b: Vec<char> = candidate.chars().collect();
+    // 送り仮名の揺れなど、ほぼ一致するものだけを拾う
+    let similarity = 1.0 - edit_distance(&a, &b) as f64 / longer as f64;
+    if similarity >= 0.75 {
+        similarity * 0.9
+    } else {
+        0.0
+    }
+}
+
+fn content_tokens(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = text
+        .split_whitespace()
+        .filter(|t| !STOPWORDS.contains(t))
+        .map(stem)
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+/// 語形変化を大まかに揃えるための簡易ステミング
+fn stem(token: &str) -> String {
+    for suffix in ["ing", "ed", "es", "s", "ly"] {
+        if let Some(base) = token.strip_suffix(suffix) {
+            if base.chars().count() >= 3 {
+                return base.to_string();
+            }
+        }
+    }
+    token.to_string()
+}
+
+fn token_overlap(answer: &str, meaning: &str) -> f64 {
+    let answer_tokens = content_tokens(answer);
+    let meaning_tokens = content_tokens(meaning);
+    if answer_tokens.is_empty() || meaning_tokens.is_empty() {
+        return 0.0;
+    }
+
+    let shared = answer_tokens
+        .iter()
+        .filter(|t| meaning_tokens.contains(t))
+        .count();
+    // Dice係数: 回答が短すぎても長すぎても下がる
+    2.0 * shared as f64 / (answer_tokens.len() + meaning_tokens.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::Json;
+
+    fn ubiquitous() -> Word {
+        let mut word = Word::for_test("word-1", "ubiquitous", "adjective");
+        word.meaning = "present or found everywhere".to_string();
+        word.translation = Some("至る所にある、遍在する".to_string());
+        word.synonyms = Json(vec!["omnipresent".to_string(), "pervasive".to_string()]);
+        word
+    }
+
+    #[test]
+    fn accepts_a_synonym_with_a_small_typo() {
+        let outcome = grade_answer(&ubiquitous(), "omnipresnt");
+        assert_eq!(outcome.verdict, Verdict::Correct);
+        assert_eq!(outcome.matched_field, Some(MatchField::Synonym));
+        assert_eq!(outcome.matched_text.as_deref(), Some("omnipresent"));
+    }
+
+    #[test]
+    fn accepts_one_of_the_translations() {
+        let outcome = grade_answer(&ubiquitous(), "遍在する");
+        assert_eq!(outcome.verdict, Verdict::Correct);
+        assert_eq!(outcome.matched_field, Some(MatchField::Translation));
+    }
+
+    #[test]
+    fn accepts_a_paraphrase_of_the_meaning() {
+        let outcome = grade_answer(&ubiquitous(), "found everywhere");
+        assert!(outcome.is_probably_correct());
+        assert_eq!(outcome.matched_field, Some(MatchField::Meaning));
+    }
+
+    #[test]
+    fn does_not_accept_the_headword_itself() {
+        let outcome = grade_answer(&ubiquitous(), "Ubiquitous");
+        assert_eq!(outcome.verdict, Verdict::Incorrect);
+        assert_eq!(outcome.score, 0.0);
+        assert_eq!(outcome.matched_field, None);
+    }
+
+    #[test]
+    fn rejects_empty_and_unrelated_answers() {
+        assert_eq!(
+            grade_answer(&ubiquitous(), "  ").verdict,
+            Verdict::Incorrect
+        );
+        assert_eq!(
+            grade_answer(&ubiquitous(), "rarely seen").verdict,
+            Verdict::Incorrect
+        );
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("same"), &chars("same")), 0);
+    }
+}
@@ -0,0 +1,631 @@
+// 英単語の語形変化 (複数形・三単現・過去形・過去分詞・進行形・比較級) を列挙する
+
+// (原形, [不規則変化形...])
+const IRREGULAR_FORMS: &[(&str, &[&str])] = &[
+    ("be", &["am", "is", "are", "was", "were", "been", "being"]),
+    ("have", &["has", "had", "having"]),
+    ("do", &["does", "did", "done", "doing"]),
+    ("go", &["goes", "went", "gone", "going"]),
+    ("arise", &["arose", "arisen"]),
+    ("awake", &["awoke", "awoken"]),
+    ("bear", &["bore", "borne", "born"]),
+    ("beat", &["beaten"]),
+    ("become", &["became"]),
+    ("begin", &["began", "begun"]),
+    ("bend", &["bent"]),
+    ("bet", &["bet"]),
+    ("bind", &["bound"]),
+    ("bite", &["bit", "bitten"]),
+    ("bleed", &["bled"]),
+    ("blow", &["blew", "blown"]),
+    ("break", &["broke", "broken"]),
+    ("breed", &["bred"]),
+    ("bring", &["brought"]),
+    ("build", &["built"]),
+    ("burn", &["burnt"]),
+    ("burst", &["burst"]),
+    ("buy", &["bought"]),
+    ("catch", &["caught"]),
+    ("choose", &["chose", "chosen"]),
+    ("cling", &["clung"]),
+    ("come", &["came"]),
+    ("cost", &["cost"]),
+    ("creep", &["crept"]),
+    ("cut", &["cut"]),
+    ("deal", &["dealt"]),
+    ("dig", &["dug"]),
+    ("draw", &["drew", "drawn"]),
+    ("dream", &["dreamt"]),
+    ("drink", &["drank", "drunk"]),
+    ("drive", &["drove", "driven"]),
+    ("eat", &["ate", "eaten"]),
+    ("fall", &["fell", "fallen"]),
+    ("feed", &["fed"]),
+    ("feel", &["felt"]),
+    ("fight", &["fought"]),
+    ("find", &["found"]),
+    ("flee", &["fled"]),
+    ("fly", &["flew", "flown"]),
+    ("forbid", &["forbade", "forbidden"]),
+    ("forget", &["forgot", "forgotten"]),
+    ("forgive", &["forgave", "forgiven"]),
+    ("freeze", &["froze", "frozen"]),
+    ("get", &["got", "gotten"]),
+    ("give", &["gave", "given"]),
+    ("grind", &["ground"]),
+    ("grow", &["grew", "grown"]),
+    ("hang", &["hung"]),
+    ("hear", &["heard"]),
+    ("hide", &["hid", "hidden"]),
+    ("hit", &["hit"]),
+    ("hold", &["held"]),
+    ("hurt", &["hurt"]),
+    ("keep", &["kept"]),
+    ("kneel", &["knelt"]),
+    ("know", &["knew", "known"]),
+    ("lay", &["laid"]),
+    ("lead", &["led"]),
+    ("lean", &["leant"]),
+    ("leap", &["leapt"]),
+    ("learn", &["learnt"]),
+    ("leave", &["left"]),
+    ("lend", &["lent"]),
+    ("let", &["let"]),
+    ("lie", &["lay", "lain", "lying"]),
+    ("light", &["lit"]),
+    ("lose", &["lost"]),
+    ("make", &["made"]),
+    ("mean", &["meant"]),
+    ("meet", &["met"]),
+    ("mislead", &["misled"]),
+    ("mistake", &["mistook", "mistaken"]),
+    ("overcome", &["overcame"]),
+    ("overtake", &["overtook", "overtaken"]),
+    ("pay", &["paid"]),
+    ("put", &["put"]),
+    ("quit", &["quit"]),
+    ("read", &["read"]),
+    ("ride", &["rode", "ridden"]),
+    ("ring", &["rang", "rung"]),
+    ("rise", &["rose", "risen"]),
+    ("run", &["ran"]),
+    ("say", &["said"]),
+    ("see", &["saw", "seen"]),
+    ("seek", &["sought"]),
+    ("sell", &["sold"]),
+    ("send", &["sent"]),
+    ("set", &["set"]),
+    ("shake", &["shook", "shaken"]),
+    ("shed", &["shed"]),
+    ("shine", &["shone"]),
+    ("shoot", &["shot"]),
+    ("show", &["shown"]),
+    ("shrink", &["shrank", "shrunk"]),
+    ("shut", &["shut"]),
+    ("sing", &["sang", "sung"]),
+    ("sink", &["sank", "sunk"]),
+    ("sit", &["sat"]),
+    ("sleep", &["slept"]),
+    ("slide", &["slid"]),
+    ("speak", &["spoke", "spoken"]),
+    ("speed", &["sped"]),
+    ("spend", &["spent"]),
+    ("spin", &["spun"]),
+    ("split", &["split"]),
+    ("spread", &["spread"]),
+    ("spring", &["sprang", "sprung"]),
+    ("stand", &["stood"]),
+    ("steal", &["stole", "stolen"]),
+    ("stick", &["stuck"]),
+    ("sting", &["stung"]),
+    ("strike", &["struck", "stricken"]),
+    ("strive", &["strove", "striven"]),
+    ("swear", &["swore", "sworn"]),
+    ("sweep", &["swept"]),
+    ("swim", &["swam", "swum"]),
+    ("swing", &["swung"]),
+    ("take", &["took", "taken"]),
+    ("teach", &["taught"]),
+    ("tear", &["tore", "torn"]),
+    ("tell", &["told"]),
+    ("think", &["thought"]),
+    ("throw", &["threw", "thrown"]),
+    ("undergo", &["underwent", "undergone"]),
+    ("understand", &["understood"]),
+    ("undertake", &["undertook", "undertaken"]),
+    ("upset", &["upset"]),
+    ("wake", &["woke", "woken"]),
+    ("wear", &["wore", "worn"]),
+    ("weave", &["wove", "woven"]),
+    ("weep", &["wept"]),
+    ("win", &["won"]),
+    ("wind", &["wound"]),
+    ("withdraw", &["withdrew", "withdrawn"]),
+    ("write", &["wrote", "written"]),
+    // 名詞
+    ("child", &["children"]),
+    ("man", &["men"]),
+    ("woman", &["women"]),
+    ("person", &["people"]),
+    ("foot", &["feet"]),
+    ("tooth", &["teeth"]),
+    ("mouse", &["mice"]),
+    ("goose", &["geese"]),
+    ("criterion", &["criteria"]),
+    ("phenomenon", &["phenomena"]),
+    ("analysis", &["analyses"]),
+    ("crisis", &["crises"]),
+    ("hypothesis", &["hypotheses"]),
+    ("thesis", &["theses"]),
+    ("datum", &["data"]),
+    ("medium", &["media"]),
+    ("life", &["lives"]),
+    ("knife", &["knives"]),
+    ("wife", &["wives"]),
+    ("leaf", &["leaves"]),
+    ("half", &["halves"]),
+    ("shelf", &["shelves"]),
+    ("thief", &["thieves"]),
+    // 形容詞・副詞
+    ("good", &["better", "best"]),
+    ("well", &["better", "best"]),
+    ("bad", &["worse", "worst"]),
+    ("badly", &["worse", "worst"]),
+    ("far", &["farther", "further", "farthest", "furthest"]),
+    ("little", &["less", "least"]),
+    ("many", &["more", "most"]),
+    ("much", &["more", "most"]),
+];
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+fn vowel_groups(word: &str) -> usize {
+    let mut groups = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c) || c == 'y';
+        if vowel && !previous_vowel {
+            groups += 1;
+        }
+        previous_vowel = vowel;
+    }
+    groups
+}
+
+/// "stop" → "stopp" のように語尾の子音を重ねる必要があるか (子音+母音+子音で終わる語)
+fn ends_with_cvc(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    let n = chars.len();
+    if n < 3 {
+        return false;
+    }
+    let (c1, v, c2) = (chars[n - 3], chars[n - 2], chars[n - 1]);
+    !is_vowel(c1) && is_vowel(v) && !is_vowel(c2) && !matches!(c2, 'w' | 'x' | 'y')
+}
+
+fn push_unique(forms: &mut Vec<String>, form: String) {
+    if !form.is_empty() && !forms.contains(&form) {
+        forms.push(form);
+    }
+}
+
+/// 生成する規則変化の種類
+#[derive(Debug, Clone, Copy, Default)]
+struct Inflections {
+    plural: bool, // 名詞の複数形
+    verb: bool,   // 三単現・過去形・進行形
+    degree: bool, // 比較級・最上級
+}
+
+impl Inflections {
+    const ALL: Inflections = Inflections {
+        plural: true,
+        verb: true,
+        degree: true,
+    };
+
+    /// 品詞 ("noun", "verb", "adjective" など) から決める。分からなければ名詞・動詞として扱う
+    fn for_parts_of_speech(parts_of_speech: &[String]) -> Inflections {
+        let mut inflections = Inflections::default();
+        for part in parts_of_speech {
+            let part = part.trim().to_lowercase();
+            if part.starts_with("adj") || part.starts_with("adv") {
+                inflections.degree = true;
+            } else if part.contains("verb") {
+                inflections.verb = true;
+            } else if part.contains("noun") {
+                inflections.plural = true;
+            }
+        }
+        if !(inflections.plural || inflections.verb || inflections.degree) {
+            inflections.plural = true;
+            inflections.verb = true;
+        }
+        inflections
+    }
+}
+
+/// 1語の変化形を原形を含めて列挙する
+///
+/// 品詞を区別せず規則変化をすべて生成するため、実在しない形も含まれうる。
+/// 文中の語を探す用途では余分な候補は害にならない。
+pub fn word_forms(lemma: &str) -> Vec<String> {
+    inflect(lemma, Inflections::ALL)
+}
+
+/// 同じ語かどうかの判定に使う変化形
+///
+/// 規則変化は品詞に合うものだけにする (work と worker、new と news を同じ語にしない)。
+/// 2文字以下の語は規則変化を付けると別の語になりやすい (be → bed) ので不規則変化だけにする。
+fn lexeme_forms(lemma: &str, parts_of_speech: &[String]) -> Vec<String> {
+    let inflections = if lemma.chars().count() <= 2 {
+        Inflections::default()
+    } else {
+        Inflections::for_parts_of_speech(parts_of_speech)
+    };
+    inflect(lemma, inflections)
+}
+
+fn inflect(lemma: &str, inflections: Inflections) -> Vec<String> {
+    let lemma = lemma.trim().to_lowercase();
+    let mut forms = vec![lemma.clone()];
+    if !is_inflectable(&lemma) {
+        return forms;
+    }
+
+    for (base, irregular) in IRREGULAR_FORMS {
+        if *base == lemma {
+            for form in *irregular {
+                push_unique(&mut forms, form.to_string());
+            }
+        }
+    }
+
+    for form in WordForm::INFLECTED {
+        if form.allowed_by(inflections) {
+            for inflected in regular_forms(&lemma, form) {
+                push_unique(&mut forms, inflected);
+            }
+        }
+    }
+
+    forms
+}
+
+fn is_inflectable(lemma: &str) -> bool {
+    !lemma.is_empty()
+        && lemma
+            .chars()
+            .all(|c| c.is_alphabetic() || c == '-' || c == '\'')
+}
+
+/// 規則変化の語形
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordForm {
+    /// 原形
+    Base,
+    /// 複数形・三単現 (-s / -es)
+    S,
+    /// 過去形・過去分詞 (-ed)
+    Past,
+    /// 進行形 (-ing)
+    Ing,
+    /// 比較級 (-er)
+    Comparative,
+    /// 最上級 (-est)
+    Superlative,
+}
+
+impl WordForm {
+    const INFLECTED: [WordForm; 5] = [
+        WordForm::S,
+        WordForm::Past,
+        WordForm::Ing,
+        WordForm::Comparative,
+        WordForm::Superlative,
+    ];
+
+    fn allowed_by(self, inflections: Inflections) -> bool {
+        match self {
+            WordForm::Base => true,
+            WordForm::S => inflections.plural || inflections.verb,
+            WordForm::Past | WordForm::Ing => inflections.verb,
+            WordForm::Comparative | WordForm::Superlative => inflections.degree,
+        }
+    }
+}
+
+/// 小文字の原形に規則変化の語尾を付ける。綴りが揺れる場合は普通の形を先頭にする
+fn regular_forms(lemma: &str, form: WordForm) -> Vec<String> {
+    let chars: Vec<char> = lemma.chars().collect();
+    let Some(&last) = chars.last() else {
+        return Vec::new();
+    };
+    let before_last = if chars.len() >= 2 {
+        Some(chars[chars.len() - 2])
+    } else {
+        None
+    };
+    let stem_without_last: String = chars[..chars.len() - 1].iter().collect();
+    let consonant_y = last == 'y' && before_last.is_some_and(|c| !is_vowel(c));
+
+    let suffix = match form {
+        WordForm::Base => return vec![lemma.to_string()],
+        // -s / -es / -ies
+        WordForm::S => {
+            return if consonant_y {
+                vec![format!("{}ies", stem_without_last)]
+            } else if lemma.ends_with('s')
+                || lemma.ends_with('x')
+                || lemma.ends_with('z')
+                || lemma.ends_with("ch")
+                || lemma.ends_with("sh")
+                || lemma.ends_with('o')
+            {
+                vec![format!("{}es", lemma), format!("{}s", lemma)]
+            } else {
+                vec![format!("{}s", lemma)]
+            };
+        }
+        WordForm::Past => "ed",
+        WordForm::Ing => "ing",
+        WordForm::Comparative => "er",
+        WordForm::Superlative => "est",
+    };
+
+    // -ed / -ing / -er / -est
+    if last == 'e' {
+        let inflected = if form != WordForm::Ing {
+            format!("{}{}", stem_without_last, suffix)
+        } else if lemma.ends_with("ie") {
+            let stem: String = chars[..chars.len() - 2].iter().collect();
+            format!("{}ying", stem)
+        } else if lemma.ends_with("ee") || lemma.ends_with("ye") || lemma.ends_with("oe") {
+            format!("{}ing", lemma)
+        } else {
+            format!("{}ing", stem_without_last)
+        };
+        return vec![inflected];
+    }
+    if consonant_y {
+        return if form == WordForm::Ing {
+            vec![format!("{}ing", lemma)]
+        } else {
+            vec![format!("{}i{}", stem_without_last, suffix)]
+        };
+    }
+
+    let mut forms = Vec::new();
+    if last == 'c' && matches!(form, WordForm::Past | WordForm::Ing) {
+        // panic → panicked
+        forms.push(format!("{}k{}", lemma, suffix));
+    }
+    // 単音節のCVCは必ず重ねる。多音節は英米で揺れるので両方を候補にする
+    let plain = format!("{}{}", lemma, suffix);
+    let doubled = format!("{}{}{}", lemma, last, suffix);
+    if !ends_with_cvc(lemma) {
+        forms.push(plain);
+    } else if vowel_groups(lemma) > 1 {
+        forms.push(plain);
+        forms.push(doubled);
+    } else {
+        forms.push(doubled);
+    }
+    forms
+}
+
+/// `word` が `lemma` のどの規則変化か。不規則変化や別の語なら None
+pub fn form_of(lemma: &str, word: &str) -> Option<WordForm> {
+    let lemma = lemma.trim().to_lowercase();
+    let word = word.trim().to_lowercase();
+    if word == lemma {
+        return Some(WordForm::Base);
+    }
+    if !is_inflectable(&lemma) {
+        return None;
+    }
+    WordForm::INFLECTED
+        .into_iter()
+        .find(|&form| regular_forms(&lemma, form).contains(&word))
+}
+
+/// `lemma` を `form` の形にする (選択肢の語形をそろえる用途)
+///
+/// 品詞に合わない変化や、不規則変化する語・2文字以下の語・more / most で比較する語など
+/// 規則変化で正しく作れない場合は None。
+pub fn inflect_as(lemma: &str, parts_of_speech: &[String], form: WordForm) -> Option<String> {
+    if form == WordForm::Base {
+        return Some(lemma.trim().to_string());
+    }
+    let lemma = lemma.trim().to_lowercase();
+    if lemma.chars().count() <= 2
+        || !is_inflectable(&lemma)
+        || IRREGULAR_FORMS.iter().any(|(base, _)| *base == lemma)
+        || !form.allowed_by(Inflections::for_parts_of_speech(parts_of_speech))
+    {
+        return None;
+    }
+    if matches!(form, WordForm::Comparative | WordForm::Superlative) && vowel_groups(&lemma) > 2 {
+        return None;
+    }
+    regular_forms(&lemma, form).into_iter().next()
+}
+
+/// 変化形から考えられる原形の候補を列挙する (変化形自身を含む)
+pub fn lemma_candidates(form: &str) -> Vec<String> {
+    let form = form.trim().to_lowercase();
+    let mut candidates = vec![form.clone()];
+
+    for (base, irregular) in IRREGULAR_FORMS {
+        if irregular.contains(&form.as_str()) {
+            push_unique(&mut candidates, base.to_string());
+        }
+    }
+
+    let strip = |suffix: &str| form.strip_suffix(suffix).map(|s| s.to_string());
+    let push_stem = |stem: Option<String>, candidates: &mut Vec<String>| {
+        if let Some(stem) = stem {
+            if stem.chars().count() >= 2 {
+                // 重ねた子音を戻した形も候補にする (stopped → stop)
+                let chars: Vec<char> = stem.chars().collect();
+                let n = chars.len();
+                if n >= 2 && chars[n - 1] == chars[n - 2] && !is_vowel(chars[n - 1]) {
+                    push_unique(candidates, chars[..n - 1].iter().collect());
+                }
+                push_unique(candidates, format!("{}e", stem));
+                push_unique(candidates, stem);
+            }
+        }
+    };
+
+    if let Some(stem) = strip("ies").or_else(|| strip("ied")) {
+        push_unique(&mut candidates, format!("{}y", stem));
+    }
+    if let Some(stem) = strip("ier").or_else(|| strip("iest")) {
+        push_unique(&mut candidates, format!("{}y", stem));
+    }
+    if let Some(stem) = strip("ying") {
+        push_unique(&mut candidates, format!("{}ie", stem));
+    }
+    push_stem(strip("es"), &mut candidates);
+    push_stem(strip("s"), &mut candidates);
+    push_stem(strip("ed"), &mut candidates);
+    push_stem(strip("ing"), &mut candidates);
+    push_stem(strip("er"), &mut candidates);
+    push_stem(strip("est"), &mut candidates);
+    if let Some(stem) = strip("ked").or_else(|| strip("king")) {
+        push_unique(&mut candidates, stem);
+    }
+
+    candidates
+}
+
+/// 登録済みの語 `known` と `other` が同じ語の変化形同士かどうか (複数語の表現は語ごとに比較する)
+///
+/// 規則変化は `known` の品詞に合うものだけを考える。
+pub fn same_lexeme(known: &str, parts_of_speech: &[String], other: &str) -> bool {
+    let known_tokens: Vec<&str> = known.split_whitespace().collect();
+    let other_tokens: Vec<&str> = other.split_whitespace().collect();
+    if known_tokens.is_empty() || known_tokens.len() != other_tokens.len() {
+        return false;
+    }
+
+    known_tokens.iter().zip(other_tokens.iter()).all(|(x, y)| {
+        let x = x.to_lowercase();
+        let y = y.to_lowercase();
+        if x == y {
+            return true;
+        }
+        // 両方を変化形に含む原形があるか
+        lemma_candidates(&x)
+            .iter()
+            .chain(lemma_candidates(&y).iter())
+            .any(|lemma| {
+                let forms = lexeme_forms(lemma, parts_of_speech);
+                forms.contains(&x) && forms.contains(&y)
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|part| part.to_string()).collect()
+    }
+
+    #[test]
+    fn word_forms_cover_regular_and_irregular_inflections() {
+        let forms = word_forms("study");
+        for form in ["study", "studies", "studied", "studying", "studier"] {
+            assert!(forms.contains(&form.to_string()), "{}", form);
+        }
+        let forms = word_forms("stop");
+        assert!(forms.contains(&"stopped".to_string()));
+        assert!(forms.contains(&"stopping".to_string()));
+        assert!(word_forms("run").contains(&"ran".to_string()));
+        assert!(word_forms("panic").contains(&"panicked".to_string()));
+        assert!(word_forms("lie").contains(&"lying".to_string()));
+    }
+
+    #[test]
+    fn lemma_candidates_undo_suffixes() {
+        assert!(lemma_candidates("studied").contains(&"study".to_string()));
+        assert!(lemma_candidates("making").contains(&"make".to_string()));
+        assert!(lemma_candidates("stopped").contains(&"stop".to_string()));
+        assert!(lemma_candidates("children").contains(&"child".to_string()));
+    }
+
+    #[test]
+    fn form_of_identifies_the_regular_inflection() {
+        assert_eq!(form_of("study", "Studied"), Some(WordForm::Past));
+        assert_eq!(form_of("stop", "stopping"), Some(WordForm::Ing));
+        assert_eq!(form_of("box", "boxes"), Some(WordForm::S));
+        assert_eq!(form_of("happy", "happiest"), Some(WordForm::Superlative));
+        assert_eq!(form_of("make", "make"), Some(WordForm::Base));
+        assert_eq!(form_of("run", "ran"), None);
+    }
+
+    #[test]
+    fn inflect_as_follows_spelling_rules_and_parts_of_speech() {
+        let verb = pos(&["verb"]);
+        assert_eq!(
+            inflect_as("carry", &verb, WordForm::Past).as_deref(),
+            Some("carried")
+        );
+        assert_eq!(
+            inflect_as("stop", &verb, WordForm::Past).as_deref(),
+            Some("stopped")
+        );
+        assert_eq!(
+            inflect_as("visit", &verb, WordForm::Ing).as_deref(),
+            Some("visiting")
+        );
+        assert_eq!(
+            inflect_as("bake", &verb, WordForm::Ing).as_deref(),
+            Some("baking")
+        );
+        assert_eq!(
+            inflect_as("panic", &verb, WordForm::Past).as_deref(),
+            Some("panicked")
+        );
+        assert_eq!(
+            inflect_as("look up", &verb, WordForm::Base).as_deref(),
+            Some("look up")
+        );
+        assert_eq!(inflect_as("table", &pos(&["noun"]), WordForm::Past), None);
+        assert_eq!(inflect_as("run", &verb, WordForm::Past), None);
+        assert_eq!(
+            inflect_as("beautiful", &pos(&["adjective"]), WordForm::Comparative),
+            None
+        );
+        assert_eq!(inflect_as("look up", &verb, WordForm::Past), None);
+    }
+
+    #[test]
+    fn same_lexeme_matches_inflections_of_the_same_word() {
+        assert!(same_lexeme("study", &pos(&["verb"]), "studied"));
+        assert!(same_lexeme("studies", &pos(&["verb"]), "study"));
+        assert!(same_lexeme("run", &pos(&["verb"]), "ran"));
+        assert!(same_lexeme("child", &pos(&["noun"]), "children"));
+        assert!(same_lexeme("box", &[], "boxes"));
+        assert!(same_lexeme("good", &pos(&["adjective"]), "better"));
+        assert!(same_lexeme("happy", &pos(&["adjective"]), "happier"));
+        assert!(same_lexeme("look up", &pos(&["phrasal verb"]), "looked up"));
+    }
+
+    #[test]
+    fn same_lexeme_rejects_different_words_that_share_a_suffix() {
+        assert!(!same_lexeme("work", &pos(&["verb", "noun"]), "worker"));
+        assert!(!same_lexeme("work", &[], "worker"));
+        assert!(!same_lexeme("teach", &pos(&["verb"]), "teacher"));
+        assert!(!same_lexeme("new", &pos(&["adjective"]), "news"));
+        assert!(!same_lexeme("good", &pos(&["adjective"]), "goods"));
+        assert!(!same_lexeme("be", &pos(&["verb"]), "bed"));
+        assert!(!same_lexeme("be", &pos(&["verb"]), "best"));
+        assert!(!same_lexeme("look up", &pos(&["verb"]), "look"));
+    }
+}
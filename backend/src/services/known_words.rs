@@ -0,0 +1,54 @@
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::services::inflection::same_lexeme;
+
+/// ユーザーが登録済みの単語
+#[derive(Debug, Clone, FromRow)]
+pub struct KnownWord {
+    pub id: String,
+    pub word: String,
+    pub part_of_speech: sqlx::types::Json<Vec<String>>,
+}
+
+/// 登録済みの単語の一覧 (AI の提案と突き合わせる)
+#[derive(Debug, Clone)]
+pub struct KnownWords {
+    words: Vec<KnownWord>,
+}
+
+impl KnownWords {
+    pub async fn load(pool: &PgPool, user_id: Uuid) -> Result<KnownWords, sqlx::Error> {
+        let words = sqlx::query_as::<_, KnownWord>(
+            "SELECT id, word, part_of_speech FROM words WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(KnownWords { words })
+    }
+
+    /// 表記が完全に一致する単語 (大文字小文字は区別しない)
+    pub fn find_exact(&self, word: &str) -> Option<&KnownWord> {
+        let word = word.trim();
+        if word.is_empty() {
+            return None;
+        }
+        self.words
+            .iter()
+            .find(|known| known.word.trim().eq_ignore_ascii_case(word))
+    }
+
+    /// 同じ語 (変化形を含む) が登録済みならその単語を返す
+    ///
+    /// 表記が完全に一致するものを優先し、無ければ変化形同士の一致を探す。
+    pub fn find(&self, word: &str) -> Option<&KnownWord> {
+        let word = word.trim();
+        self.find_exact(word).or_else(|| {
+            self.words
+                .iter()
+                .find(|known| same_lexeme(known.word.trim(), &known.part_of_speech, word))
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use shuttle_axum::axum::http::StatusCode;
+
+use crate::handlers::ai_handler;
+use crate::models::word::{Word, WORD_COLUMNS};
+use crate::models::AppState;
+
+/// 今回の復習でリーチになったか (忘却回数がしきい値に達し、まだリーチでない)
+pub fn became_leech(word: &Word, lapses_after: i32, threshold: i32) -> bool {
+    !word.is_leech && threshold > 0 && lapses_after >= threshold
+}
+
+/// AIでリーチ用の覚え方を生成して単語に保存する
+pub async fn refresh_leech_aids(
+    app_state: &AppState,
+    word: &Word,
+) -> Result<Word, (StatusCode, String)> {
+    let aids = ai_handler::generate_leech_aids(app_state, word).await?;
+
+    let sql = format!(
+        "UPDATE words SET mnemonic = $3, etymology_hint = $4, contrasting_example = $5, updated_at = NOW()
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        WORD_COLUMNS
+    );
+    sqlx::query_as::<_, Word>(&sql)
+        .bind(&word.id)
+        .bind(word.user_id)
+        .bind(&aids.mnemonic)
+        .bind(&aids.etymology_hint)
+        .bind(&aids.contrasting_example)
+        .fetch_optional(&app_state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Word not found".to_string()))
+}
+
+/// リーチ用の覚え方をバックグラウンドで生成する (復習のレスポンスを待たせない)
+pub fn spawn_leech_aids(app_state: &AppState, word: Word) {
+    let app_state = app_state.clone();
+    tokio::spawn(async move {
+        if let Err((status, message)) = refresh_leech_aids(&app_state, &word).await {
+            println!(
+                "Failed to generate leech aids for word {} ({}): {}",
+                word.id, status, message
+            );
+        }
+    });
+}
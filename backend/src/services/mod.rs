@@ -0,0 +1,15 @@
+pub mod ai_cache;
+pub mod ai_stream;
+pub mod ai_usage;
+pub mod cloze;
+pub mod exam;
+pub mod forecast;
+pub mod grader;
+pub mod inflection;
+pub mod known_words;
+pub mod leech;
+pub mod placement;
+pub mod planner;
+pub mod review;
+pub mod scheduler;
+pub mod streak;
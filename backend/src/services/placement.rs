@@ -0,0 +1,463 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::models::user::CefrLevel;
+
+/// 1つの頻度帯に含まれる語数
+pub const BAND_SIZE: u32 = 1000;
+/// 最初に出題する頻度帯 (0始まり。1000-2000語)
+pub const START_BAND: usize = 1;
+/// 1つの頻度帯で続けて出題する実在語の数
+pub const BLOCK_SIZE: usize = 5;
+/// 出題数の上限
+pub const MAX_ITEMS: usize = 50;
+
+// この割合以上正解したら上の頻度帯へ、未満なら下の頻度帯へ
+const MOVE_UP_RATIO: f64 = 0.8;
+const MOVE_DOWN_RATIO: f64 = 0.5;
+// 何問ごとに擬似語を混ぜるか
+const PSEUDOWORD_EVERY: usize = 4;
+const MULTIPLE_CHOICE_COUNT: usize = 4;
+
+/// 頻度帯ごとの単語と短い語義 (1000語ごと、8000語まで)
+pub const BANDS: [&[(&str, &str)]; 8] = [
+    &[
+        ("house", "a building where people live"),
+        ("water", "the clear liquid in rivers and rain"),
+        ("answer", "a reply to a question"),
+        ("child", "a young person"),
+        ("early", "before the usual time"),
+        ("remember", "to keep something in your mind"),
+        ("carry", "to hold something while moving"),
+        ("money", "coins and notes used to buy things"),
+    ],
+    &[
+        ("borrow", "to take something and promise to return it"),
+        ("honest", "always telling the truth"),
+        ("mistake", "something done wrongly"),
+        ("weather", "the conditions of sun, rain and wind"),
+        ("crowd", "a large group of people"),
+        ("annual", "happening once a year"),
+        ("declare", "to state something officially"),
+        ("silence", "complete quiet"),
+    ],
+    &[
+        ("reluctant", "not willing to do something"),
+        ("harvest", "the gathering of crops"),
+        ("frighten", "to make someone afraid"),
+        ("grief", "deep sadness after a loss"),
+        ("tidy", "neat and in order"),
+        ("swallow", "to make food go down your throat"),
+        ("neglect", "to fail to take care of"),
+        ("bargain", "something bought cheaply"),
+    ],
+    &[
+        ("coward", "a person who lacks courage"),
+        ("thrive", "to grow or develop well"),
+        ("ambiguous", "having more than one possible meaning"),
+        ("erode", "to wear away gradually"),
+        ("scarce", "not enough to meet demand"),
+        ("mourn", "to feel sad about a death"),
+        ("vessel", "a ship or large boat"),
+        ("wary", "careful because of possible danger"),
+    ],
+    &[
+        ("lenient", "not strict in punishing"),
+        ("plight", "a difficult or dangerous situation"),
+        ("hamper", "to slow down progress"),
+        ("frugal", "careful not to waste money"),
+        ("dwindle", "to become gradually smaller"),
+        ("sulk", "to be silently bad-tempered"),
+        ("mundane", "ordinary and dull"),
+        ("rebuke", "to criticise sharply"),
+    ],
+    &[
+        ("meticulous", "very careful about details"),
+        ("placate", "to make someone less angry"),
+        ("ominous", "suggesting something bad will happen"),
+        ("brittle", "hard but easily broken"),
+        ("feign", "to pretend to feel something"),
+        ("gist", "the main idea"),
+        ("rapport", "a friendly understanding between people"),
+        ("tenacious", "not giving up easily"),
+    ],
+    &[
+        ("cajole", "to persuade by flattery"),
+        ("ostracize", "to exclude from a group"),
+        ("quaint", "charmingly old-fashioned"),
+        ("fervent", "showing very strong feeling"),
+        ("hapless", "unlucky"),
+        ("jostle", "to push roughly in a crowd"),
+        ("squalid", "very dirty and unpleasant"),
+        ("wane", "to become gradually weaker"),
+    ],
+    &[
+        ("obsequious", "too eager to please or obey"),
+        ("ebullient", "cheerful and full of energy"),
+        ("truculent", "eager to argue or fight"),
+        ("pernicious", "harmful in a gradual way"),
+        ("recalcitrant", "refusing to obey"),
+        ("lugubrious", "looking sad and gloomy"),
+        ("obfuscate", "to make something unclear"),
+        ("perfunctory", "done quickly without care"),
+    ],
+];
+
+/// 実在しない英語風の語。「知っている」と答えた割合で推測による回答を補正する
+pub const PSEUDOWORDS: &[&str] = &[
+    "mensible",
+    "kermshaw",
+    "plaintion",
+    "fellick",
+    "scornity",
+    "rudition",
+    "grondle",
+    "stoffer",
+    "trimbly",
+    "pelloque",
+    "abrisp",
+    "hulvent",
+];
+
+/// 問題の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    /// 知っている単語か (はい/いいえ)
+    YesNo,
+    /// 語義を4択から選ぶ
+    MultipleChoice,
+}
+
+impl ItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemKind::YesNo => "yes_no",
+            ItemKind::MultipleChoice => "multiple_choice",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "yes_no" => Some(ItemKind::YesNo),
+            "multiple_choice" => Some(ItemKind::MultipleChoice),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacementItem {
+    pub band: usize,
+    pub kind: ItemKind,
+    pub word: String,
+    pub is_real: bool,
+    pub choices: Vec<String>,
+    pub correct_index: Option<usize>,
+}
+
+/// 回答済みの問題
+#[derive(Debug, Clone)]
+pub struct AnsweredItem {
+    pub band: usize,
+    pub word: String,
+    pub is_real: bool,
+    /// 実在語なら「知っている」・正しい語義を選んだ、擬似語なら「知らない」と答えた
+    pub correct: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// 次に出題する頻度帯
+    pub band: usize,
+    /// 現在の頻度帯で出題済みの実在語の数
+    pub answered_in_block: usize,
+    /// 語彙の境界が定まったか
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacementResult {
+    pub cefr_level: CefrLevel,
+    pub vocab_estimate: u32,
+}
+
+/// 回答履歴をたどって次に出題する頻度帯を決める
+///
+/// 頻度帯ごとに実在語を `BLOCK_SIZE` 問出し、正解率が高ければ上へ、低ければ下へ移る。
+/// 一度終えた頻度帯に戻る場合や、最上位・最下位で止まった場合は境界が定まったとみなす。
+pub fn progress(history: &[AnsweredItem]) -> Progress {
+    let mut band = START_BAND;
+    let mut completed: Vec<usize> = Vec::new();
+    let (mut asked, mut correct) = (0, 0);
+
+    for item in history.iter().filter(|item| item.is_real) {
+        asked += 1;
+        if item.correct {
+            correct += 1;
+        }
+        if asked < BLOCK_SIZE {
+            continue;
+        }
+
+        completed.push(band);
+        let ratio = correct as f64 / asked as f64;
+        let next = if ratio >= MOVE_UP_RATIO && band + 1 < BANDS.len() {
+            Some(band + 1)
+        } else if ratio < MOVE_DOWN_RATIO && band > 0 {
+            Some(band - 1)
+        } else {
+            None
+        };
+        match next {
+            Some(next) if !completed.contains(&next) => band = next,
+            _ => {
+                return Progress {
+                    band,
+                    answered_in_block: 0,
+                    finished: true,
+                }
+            }
+        }
+        (asked, correct) = (0, 0);
+    }
+
+    Progress {
+        band,
+        answered_in_block: asked,
+        finished: history.len() >= MAX_ITEMS,
+    }
+}
+
+/// 次の問題を作る。出題できる単語が残っていなければ None
+pub fn next_item<R: Rng>(
+    history: &[AnsweredItem],
+    progress: Progress,
+    rng: &mut R,
+) -> Option<PlacementItem> {
+    let asked = |word: &str| history.iter().any(|item| item.word == word);
+
+    // 実在語の途中に一定間隔で擬似語を混ぜる
+    if history.len() % PSEUDOWORD_EVERY == PSEUDOWORD_EVERY - 1 {
+        let remaining: Vec<&&str> = PSEUDOWORDS.iter().filter(|w| !asked(w)).collect();
+        if let Some(word) = remaining.choose(rng) {
+            return Some(PlacementItem {
+                band: progress.band,
+                kind: ItemKind::YesNo,
+                word: word.to_string(),
+                is_real: false,
+                choices: Vec::new(),
+                correct_index: None,
+            });
+        }
+    }
+
+    let entries = BANDS[progress.band];
+    let remaining: Vec<&(&str, &str)> = entries.iter().filter(|(w, _)| !asked(w)).collect();
+    let &(word, gloss) = *remaining.choose(rng)?;
+
+    // 同じ頻度帯の中で、はい/いいえと4択を交互に出す
+    if progress.answered_in_block.is_multiple_of(2) {
+        return Some(PlacementItem {
+            band: progress.band,
+            kind: ItemKind::YesNo,
+            word: word.to_string(),
+            is_real: true,
+            choices: Vec::new(),
+            correct_index: None,
+        });
+    }
+
+    let mut choices: Vec<String> = entries
+        .iter()
+        .filter(|(w, _)| *w != word)
+        .map(|(_, g)| g.to_string())
+        .collect::<Vec<_>>()
+        .choose_multiple(rng, MULTIPLE_CHOICE_COUNT - 1)
+        .cloned()
+        .collect();
+    let correct_index = rng.gen_range(0..=choices.len());
+    choices.insert(correct_index, gloss.to_string());
+
+    Some(PlacementItem {
+        band: progress.band,
+        kind: ItemKind::MultipleChoice,
+        word: word.to_string(),
+        is_real: true,
+        choices,
+        correct_index: Some(correct_index),
+    })
+}
+
+/// 語彙数から CEFR レベルを推定する (Milton 2010 の対応表に基づく目安)
+pub fn cefr_from_vocab_size(vocab: u32) -> CefrLevel {
+    match vocab {
+        0..=1499 => CefrLevel::A1,
+        1500..=2499 => CefrLevel::A2,
+        2500..=3249 => CefrLevel::B1,
+        3250..=3749 => CefrLevel::B2,
+        3750..=4499 => CefrLevel::C1,
+        _ => CefrLevel::C2,
+    }
+}
+
+/// 回答履歴から語彙数と CEFR レベルを推定する
+///
+/// 頻度帯ごとの正解率を擬似語への「知っている」の割合で補正し、帯の語数を掛けて合計する。
+/// 出題した帯より下はすべて知っている、上は知らないとみなす。
+pub fn estimate(history: &[AnsweredItem]) -> PlacementResult {
+    let pseudo: Vec<&AnsweredItem> = history.iter().filter(|item| !item.is_real).collect();
+    let false_alarm_rate = if pseudo.is_empty() {
+        0.0
+    } else {
+        pseudo.iter().filter(|item| !item.correct).count() as f64 / pseudo.len() as f64
+    };
+
+    let mut ratios: Vec<Option<f64>> = vec![None; BANDS.len()];
+    for (band, ratio) in ratios.iter_mut().enumerate() {
+        let items: Vec<&AnsweredItem> = history
+            .iter()
+            .filter(|item| item.is_real && item.band == band)
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+        let hit_rate = items.iter().filter(|item| item.correct).count() as f64 / items.len() as f64;
+        *ratio = Some(if false_alarm_rate >= 1.0 {
+            0.0
+        } else {
+            ((hit_rate - false_alarm_rate) / (1.0 - false_alarm_rate)).clamp(0.0, 1.0)
+        });
+    }
+
+    let lowest = ratios.iter().position(Option::is_some);
+    let vocab: f64 = ratios
+        .iter()
+        .enumerate()
+        .map(|(band, ratio)| match (ratio, lowest) {
+            (Some(ratio), _) => *ratio,
+            (None, Some(lowest)) if band < lowest => 1.0,
+            _ => 0.0,
+        })
+        .sum::<f64>()
+        * BAND_SIZE as f64;
+
+    let vocab_estimate = ((vocab / 50.0).round() * 50.0) as u32;
+    PlacementResult {
+        cefr_level: cefr_from_vocab_size(vocab_estimate),
+        vocab_estimate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn real(band: usize, correct: bool) -> AnsweredItem {
+        AnsweredItem {
+            band,
+            word: BANDS[band][0].0.to_string(),
+            is_real: true,
+            correct,
+        }
+    }
+
+    fn pseudo(correct: bool) -> AnsweredItem {
+        AnsweredItem {
+            band: START_BAND,
+            word: PSEUDOWORDS[0].to_string(),
+            is_real: false,
+            correct,
+        }
+    }
+
+    /// 頻度帯 `band` で実在語を `BLOCK_SIZE` 問、先頭の `correct` 問だけ正解する
+    fn block(band: usize, correct: usize) -> Vec<AnsweredItem> {
+        (0..BLOCK_SIZE).map(|i| real(band, i < correct)).collect()
+    }
+
+    #[test]
+    fn starts_at_the_start_band() {
+        assert_eq!(
+            progress(&[]),
+            Progress {
+                band: START_BAND,
+                answered_in_block: 0,
+                finished: false,
+            }
+        );
+    }
+
+    #[test]
+    fn moves_up_and_stops_when_returning_to_a_finished_band() {
+        let mut history = block(1, 5);
+        history.push(pseudo(true));
+        let after_first = progress(&history);
+        assert_eq!(after_first.band, 2);
+        assert_eq!(after_first.answered_in_block, 0);
+        assert!(!after_first.finished);
+
+        history.extend(block(2, 1));
+        let after_second = progress(&history);
+        assert_eq!(after_second.band, 2);
+        assert!(after_second.finished);
+    }
+
+    #[test]
+    fn counts_only_real_words_in_a_block() {
+        let history = [real(1, true), pseudo(false), real(1, true)];
+        let current = progress(&history);
+        assert_eq!(current.band, 1);
+        assert_eq!(current.answered_in_block, 2);
+        assert!(!current.finished);
+    }
+
+    #[test]
+    fn finishes_on_a_middle_score_or_at_either_end() {
+        assert!(progress(&block(1, 3)).finished);
+
+        let mut history = block(1, 0);
+        history.extend(block(0, 0));
+        let bottom = progress(&history);
+        assert_eq!(bottom.band, 0);
+        assert!(bottom.finished);
+
+        let history: Vec<AnsweredItem> = (1..BANDS.len()).flat_map(|band| block(band, 5)).collect();
+        let top = progress(&history);
+        assert_eq!(top.band, BANDS.len() - 1);
+        assert!(top.finished);
+    }
+
+    #[test]
+    fn counts_the_bands_below_the_first_one_asked_as_known() {
+        let mut history = block(1, 5);
+        history.extend(block(2, 0));
+        let result = estimate(&history);
+        assert_eq!(result.vocab_estimate, 2000);
+        assert_eq!(result.cefr_level, CefrLevel::A2);
+    }
+
+    #[test]
+    fn corrects_for_claiming_to_know_pseudowords() {
+        let mut history = block(1, 5);
+        history.extend(block(2, 4));
+        assert_eq!(estimate(&history).vocab_estimate, 2800);
+
+        // 擬似語の半分を「知っている」と答えたら、その分だけ割り引く
+        history.extend([pseudo(false), pseudo(true)]);
+        let result = estimate(&history);
+        assert_eq!(result.vocab_estimate, 2600);
+        assert_eq!(result.cefr_level, CefrLevel::B1);
+
+        // 擬似語をすべて「知っている」と答えたら、出題した帯はすべて知らないとみなす
+        let mut history = block(2, 5);
+        history.push(pseudo(false));
+        assert_eq!(estimate(&history).vocab_estimate, 2000);
+    }
+
+    #[test]
+    fn estimates_nothing_without_answers() {
+        let result = estimate(&[]);
+        assert_eq!(result.vocab_estimate, 0);
+        assert_eq!(result.cefr_level, CefrLevel::A1);
+    }
+}
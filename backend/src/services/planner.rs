@@ -0,0 +1,172 @@
+use crate::models::word::Word;
+use crate::services::grader::edit_distance;
+
+/// 関連語を隣り合わせないために先読みする項目数
+const LOOKAHEAD: usize = 4;
+
+/// 学習プランの項目の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanItemKind {
+    Review, // 期限が来た復習
+    New,    // 新規単語
+}
+
+impl PlanItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanItemKind::Review => "review",
+            PlanItemKind::New => "new",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanEntry {
+    pub kind: PlanItemKind,
+    pub word_id: String,
+}
+
+/// 混同しやすい関連語か (類義語として登録されている、または綴りがよく似ている)
+pub fn related(a: &Word, b: &Word) -> bool {
+    let word_a = a.word.trim().to_lowercase();
+    let word_b = b.word.trim().to_lowercase();
+
+    let is_synonym = |word: &Word, other: &str| {
+        word.synonyms
+            .iter()
+            .any(|s| s.trim().to_lowercase() == other)
+    };
+    if is_synonym(a, &word_b) || is_synonym(b, &word_a) {
+        return true;
+    }
+
+    // affect / effect, adapt / adopt のような綴りの近い語
+    let chars_a: Vec<char> = word_a.chars().collect();
+    let chars_b: Vec<char> = word_b.chars().collect();
+    let shorter = chars_a.len().min(chars_b.len());
+    if shorter < 4 {
+        return false;
+    }
+    let max_distance = if shorter < 6 { 1 } else { 2 };
+    edit_distance(&chars_a, &chars_b) <= max_distance
+}
+
+/// 今日の学習プランを組み立てる
+///
+/// 新規単語は復習の間に均等に散らし、そのうえで関連語が連続しないように
+/// 近くの項目と入れ替える。入力の順序 (復習は期限順、新規は追加順) はなるべく保つ。
+pub fn build_plan(reviews: &[Word], new_words: &[Word]) -> Vec<PlanEntry> {
+    let (r, n) = (reviews.len(), new_words.len());
+
+    // 復習 j は (j+1)/(r+1)、新規 i は (i+1)/(n+1) の位置に置く
+    let mut merged: Vec<(PlanItemKind, &Word)> = Vec::with_capacity(r + n);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < r {
+        let take_review = j < r && (i >= n || (j + 1) * (n + 1) <= (i + 1) * (r + 1));
+        if take_review {
+            merged.push((PlanItemKind::Review, &reviews[j]));
+            j += 1;
+        } else {
+            merged.push((PlanItemKind::New, &new_words[i]));
+            i += 1;
+        }
+    }
+
+    let mut plan: Vec<(PlanItemKind, &Word)> = Vec::with_capacity(merged.len());
+    while !merged.is_empty() {
+        let index = match plan.last() {
+            Some((_, previous)) => merged
+                .iter()
+                .take(LOOKAHEAD)
+                .position(|(_, word)| !related(previous, word))
+                .unwrap_or(0),
+            None => 0,
+        };
+        plan.push(merged.remove(index));
+    }
+
+    plan.into_iter()
+        .map(|(kind, word)| PlanEntry {
+            kind,
+            word_id: word.id.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::Json;
+
+    fn word(id: &str, headword: &str) -> Word {
+        Word::for_test(id, headword, "verb")
+    }
+
+    fn kinds(plan: &[PlanEntry]) -> Vec<PlanItemKind> {
+        plan.iter().map(|entry| entry.kind).collect()
+    }
+
+    fn ids(plan: &[PlanEntry]) -> Vec<&str> {
+        plan.iter().map(|entry| entry.word_id.as_str()).collect()
+    }
+
+    #[test]
+    fn spreads_new_words_evenly_between_reviews() {
+        let reviews = [
+            word("r1", "borrow"),
+            word("r2", "climb"),
+            word("r3", "gather"),
+            word("r4", "listen"),
+        ];
+        let new_words = [word("n1", "whisper"), word("n2", "polish")];
+        let plan = build_plan(&reviews, &new_words);
+
+        use PlanItemKind::{New, Review};
+        assert_eq!(kinds(&plan), [Review, New, Review, Review, New, Review]);
+        assert_eq!(ids(&plan), ["r1", "n1", "r2", "r3", "n2", "r4"]);
+    }
+
+    #[test]
+    fn keeps_the_input_order_when_only_one_kind_is_given() {
+        let reviews = [word("r1", "borrow"), word("r2", "climb")];
+        assert_eq!(ids(&build_plan(&reviews, &[])), ["r1", "r2"]);
+        assert_eq!(ids(&build_plan(&[], &reviews)), ["r1", "r2"]);
+        assert!(build_plan(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn separates_words_that_are_easily_confused() {
+        let reviews = [
+            word("r1", "affect"),
+            word("r2", "effect"),
+            word("r3", "borrow"),
+        ];
+        let plan = build_plan(&reviews, &[]);
+        assert_eq!(ids(&plan), ["r1", "r3", "r2"]);
+    }
+
+    #[test]
+    fn keeps_the_order_when_every_nearby_word_is_related() {
+        let reviews = [
+            word("r1", "adapt"),
+            word("r2", "adopt"),
+            word("r3", "adept"),
+        ];
+        let plan = build_plan(&reviews, &[]);
+        assert_eq!(ids(&plan), ["r1", "r2", "r3"]);
+    }
+
+    #[test]
+    fn relates_synonyms_and_similar_spellings() {
+        let mut big = word("1", "big");
+        big.synonyms = Json(vec!["Large".to_string()]);
+        assert!(related(&big, &word("2", "large")));
+        assert!(related(&word("2", "large"), &big));
+
+        assert!(related(&word("1", "adapt"), &word("2", "Adopt")));
+        assert!(related(&word("1", "accept"), &word("2", "except")));
+        // 短い語は綴りが近くても別の語とみなす
+        assert!(!related(&word("1", "cat"), &word("2", "cut")));
+        assert!(!related(&word("1", "borrow"), &word("2", "climb")));
+    }
+}
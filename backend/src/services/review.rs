@@ -0,0 +1,85 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::models::activity::{Activity, ActivityKind};
+use crate::models::study_plan::StudyPlan;
+use crate::models::user_settings::UserSettings;
+use crate::models::word::{Word, WordStatus, WORD_COLUMNS};
+use crate::models::AppState;
+use crate::services::leech;
+use crate::services::scheduler::{self, ReviewAnswer, ReviewState};
+
+/// 復習結果をライトナーの箱に反映し、学習ログに記録する
+///
+/// 単語のスケジュールを動かす学習モードはこの関数を通して記録する。
+/// 忘却回数がしきい値に達した単語はリーチとして印を付け、覚え方をAIで生成する。
+/// 今日の学習プランに含まれる単語なら、その項目を学習済みにする。
+pub async fn record_review(
+    app_state: &AppState,
+    user_id: Uuid,
+    word: &Word,
+    answer: ReviewAnswer,
+) -> Result<Word, sqlx::Error> {
+    let pool = &app_state.pool;
+    let settings = UserSettings::get_or_create(pool, user_id).await?;
+
+    let state = ReviewState::from(word);
+    let next = scheduler::schedule_leitner(state, answer, &settings.leitner_intervals);
+
+    let current_status = WordStatus::parse(&word.status).unwrap_or(WordStatus::New);
+    let mut status = scheduler::status_after_review(
+        current_status,
+        &next,
+        answer,
+        settings.leitner_intervals.len(),
+    );
+
+    let new_leech = leech::became_leech(word, next.lapses, settings.leech_threshold);
+    if new_leech && settings.leech_auto_suspend {
+        status = WordStatus::Suspended;
+    }
+
+    let now = Utc::now();
+    let due_at = now + Duration::days(next.interval_days as i64);
+
+    let sql = format!(
+        "UPDATE words SET
+            interval_days = $3, repetitions = $4, lapses = $5, leitner_box = $6,
+            due_at = $7, last_reviewed_at = $8, updated_at = $8, status = $9,
+            is_leech = is_leech OR $10
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        WORD_COLUMNS
+    );
+    let updated = sqlx::query_as::<_, Word>(&sql)
+        .bind(&word.id)
+        .bind(user_id)
+        .bind(next.interval_days)
+        .bind(next.repetitions)
+        .bind(next.lapses)
+        .bind(next.leitner_box)
+        .bind(due_at)
+        .bind(now)
+        .bind(status.as_str())
+        .bind(new_leech)
+        .fetch_one(pool)
+        .await?;
+
+    Activity::record(
+        pool,
+        user_id,
+        ActivityKind::Review,
+        Some(&word.id),
+        Some(answer.correct),
+    )
+    .await?;
+
+    let today = settings.local_today(pool).await?;
+    StudyPlan::mark_completed(pool, user_id, today, &word.id).await?;
+
+    if new_leech {
+        leech::spawn_leech_aids(app_state, updated.clone());
+    }
+
+    Ok(updated)
+}
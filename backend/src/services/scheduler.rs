@@ -0,0 +1,212 @@
+use crate::models::word::{Word, WordStatus};
+
+/// この回数以上連続で正解した単語は復習段階とみなす
+const REVIEWING_REPETITIONS: i32 = 2;
+
+/// 単語ごとの復習スケジュールの状態
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReviewState {
+    pub interval_days: i32,
+    pub repetitions: i32,
+    pub lapses: i32,
+    pub leitner_box: i32,
+}
+
+impl From<&Word> for ReviewState {
+    fn from(word: &Word) -> Self {
+        Self {
+            interval_days: word.interval_days,
+            repetitions: word.repetitions,
+            lapses: word.lapses,
+            leitner_box: word.leitner_box,
+        }
+    }
+}
+
+/// 新しく追加された単語の状態 (words テーブルのデフォルト値と同じ)
+impl Default for ReviewState {
+    fn default() -> Self {
+        Self {
+            interval_days: 0,
+            repetitions: 0,
+            lapses: 0,
+            leitner_box: 1,
+        }
+    }
+}
+
+/// 復習結果
+#[derive(Debug, Clone, Copy)]
+pub struct ReviewAnswer {
+    pub correct: bool,
+}
+
+/// ライトナー方式で次の状態を求める
+///
+/// 正解なら1つ上の箱へ、不正解なら最初の箱へ戻す。間隔は移動先の箱の設定値。
+pub fn schedule_leitner(
+    state: ReviewState,
+    answer: ReviewAnswer,
+    intervals: &[i32],
+) -> ReviewState {
+    let box_count = intervals.len().max(1) as i32;
+    let current = state.leitner_box.clamp(1, box_count);
+    let mut next = state;
+
+    if answer.correct {
+        next.leitner_box = (current + 1).min(box_count);
+        next.repetitions += 1;
+    } else {
+        next.leitner_box = 1;
+        next.repetitions = 0;
+        next.lapses += 1;
+    }
+    next.interval_days = leitner_interval(intervals, next.leitner_box);
+
+    next
+}
+
+/// 箱番号 (1始まり) の復習間隔。設定が無ければ 1 日
+pub fn leitner_interval(intervals: &[i32], leitner_box: i32) -> i32 {
+    intervals
+        .get((leitner_box.max(1) - 1) as usize)
+        .copied()
+        .or_else(|| intervals.last().copied())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// ライトナーの間隔設定が妥当か (1箱以上・各間隔が1日以上で昇順)
+pub fn validate_leitner_intervals(intervals: &[i32]) -> Result<(), String> {
+    if intervals.is_empty() || intervals.len() > 10 {
+        return Err("Leitner mode needs between 1 and 10 boxes".to_string());
+    }
+    if intervals.iter().any(|&days| days < 1) {
+        return Err("Leitner intervals must be at least 1 day".to_string());
+    }
+    if intervals.windows(2).any(|w| w[0] > w[1]) {
+        return Err("Leitner intervals must not decrease from one box to the next".to_string());
+    }
+    Ok(())
+}
+
+/// 復習後のステータスを決める
+///
+/// 一時停止・対象外は手動でのみ変更する。不正解なら学習中に戻し、
+/// 正解なら連続正解数と箱 (最後の箱なら習得済み) で段階を上げる。
+pub fn status_after_review(
+    current: WordStatus,
+    next: &ReviewState,
+    answer: ReviewAnswer,
+    leitner_box_count: usize,
+) -> WordStatus {
+    if current.is_manual() {
+        return current;
+    }
+    if !answer.correct {
+        return WordStatus::Learning;
+    }
+
+    if next.leitner_box >= leitner_box_count.max(1) as i32 {
+        WordStatus::Mastered
+    } else if next.repetitions >= REVIEWING_REPETITIONS {
+        WordStatus::Reviewing
+    } else {
+        WordStatus::Learning
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVALS: &[i32] = &[1, 2, 4, 8, 16];
+
+    fn in_box(leitner_box: i32) -> ReviewState {
+        ReviewState {
+            leitner_box,
+            ..ReviewState::default()
+        }
+    }
+
+    #[test]
+    fn correct_answers_move_up_one_box() {
+        let next = schedule_leitner(in_box(2), ReviewAnswer { correct: true }, INTERVALS);
+        assert_eq!(next.leitner_box, 3);
+        assert_eq!(next.interval_days, 4);
+        assert_eq!(next.repetitions, 1);
+
+        let top = schedule_leitner(in_box(5), ReviewAnswer { correct: true }, INTERVALS);
+        assert_eq!(top.leitner_box, 5);
+        assert_eq!(top.interval_days, 16);
+    }
+
+    #[test]
+    fn wrong_answers_go_back_to_the_first_box() {
+        let state = ReviewState {
+            repetitions: 3,
+            ..in_box(4)
+        };
+        let next = schedule_leitner(state, ReviewAnswer { correct: false }, INTERVALS);
+        assert_eq!(next.leitner_box, 1);
+        assert_eq!(next.interval_days, 1);
+        assert_eq!(next.repetitions, 0);
+        assert_eq!(next.lapses, 1);
+    }
+
+    #[test]
+    fn words_beyond_the_last_box_use_the_last_interval() {
+        // 箱の数を減らした後の単語
+        let next = schedule_leitner(in_box(8), ReviewAnswer { correct: true }, &[1, 3]);
+        assert_eq!(next.leitner_box, 2);
+        assert_eq!(leitner_interval(&[1, 3], 7), 3);
+        assert_eq!(leitner_interval(&[], 1), 1);
+    }
+
+    #[test]
+    fn validates_leitner_intervals() {
+        assert!(validate_leitner_intervals(INTERVALS).is_ok());
+        assert!(validate_leitner_intervals(&[]).is_err());
+        assert!(validate_leitner_intervals(&[1; 11]).is_err());
+        assert!(validate_leitner_intervals(&[0, 1]).is_err());
+        assert!(validate_leitner_intervals(&[4, 2]).is_err());
+    }
+
+    #[test]
+    fn status_follows_review_results() {
+        let correct = ReviewAnswer { correct: true };
+        let learning = ReviewState {
+            repetitions: 1,
+            ..in_box(2)
+        };
+        let reviewing = ReviewState {
+            repetitions: 2,
+            ..in_box(3)
+        };
+        assert_eq!(
+            status_after_review(WordStatus::New, &learning, correct, 5),
+            WordStatus::Learning
+        );
+        assert_eq!(
+            status_after_review(WordStatus::Learning, &reviewing, correct, 5),
+            WordStatus::Reviewing
+        );
+        assert_eq!(
+            status_after_review(WordStatus::Reviewing, &in_box(5), correct, 5),
+            WordStatus::Mastered
+        );
+        assert_eq!(
+            status_after_review(
+                WordStatus::Mastered,
+                &in_box(1),
+                ReviewAnswer { correct: false },
+                5
+            ),
+            WordStatus::Learning
+        );
+        assert_eq!(
+            status_after_review(WordStatus::Suspended, &in_box(5), correct, 5),
+            WordStatus::Suspended
+        );
+    }
+}
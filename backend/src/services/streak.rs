@@ -0,0 +1,194 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+/// ユーザーのローカル日付ごとの学習量
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DayActivity {
+    pub new_words: i64,
+    pub reviews: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DailyGoals {
+    pub new_words: i64,
+    pub reviews: i64,
+}
+
+impl DailyGoals {
+    /// 0 でない目標をすべて達成していれば true。目標がすべて 0 なら何か学習していれば true
+    pub fn is_met(&self, activity: &DayActivity) -> bool {
+        if self.new_words <= 0 && self.reviews <= 0 {
+            return activity.new_words + activity.reviews > 0;
+        }
+        activity.new_words >= self.new_words.max(0) && activity.reviews >= self.reviews.max(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreakSummary {
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// フリーズで守られた日 (古い順)
+    pub frozen_days: Vec<NaiveDate>,
+    pub freezes_used_this_month: u32,
+    pub freezes_remaining_this_month: u32,
+}
+
+/// 目標を達成した日から現在・最長ストリークを計算する
+///
+/// 達成したかどうかはその日に有効だった目標で判定しておくこと。
+/// 目標未達の日が続いても、その日数分のフリーズがそれぞれの月に残っていれば
+/// ストリークは途切れない (フリーズした日はストリーク日数に数えない)。
+/// 今日はまだ終わっていないので、未達でもストリークを途切れさせない。
+pub fn compute_streak(
+    met_days: &BTreeSet<NaiveDate>,
+    today: NaiveDate,
+    freezes_per_month: u32,
+) -> StreakSummary {
+    let met = |day: NaiveDate| met_days.contains(&day);
+    let month_of = |day: NaiveDate| (day.year(), day.month());
+
+    let mut used: HashMap<(i32, u32), u32> = HashMap::new();
+    let mut frozen_days = Vec::new();
+    let mut run = 0u32;
+    let mut longest = 0u32;
+
+    let first_met = met_days.iter().copied().find(|&day| day <= today);
+    if let Some(first) = first_met {
+        let mut day = first;
+        while day < today {
+            if met(day) {
+                run += 1;
+                longest = longest.max(run);
+                day = day.succ_opt().unwrap();
+                continue;
+            }
+
+            // 未達の日が続く区間 [day, gap_end) をまとめて扱う
+            let mut gap_end = day;
+            while gap_end < today && !met(gap_end) {
+                gap_end = gap_end.succ_opt().unwrap();
+            }
+
+            let mut needed: HashMap<(i32, u32), u32> = HashMap::new();
+            let mut d = day;
+            while d < gap_end {
+                *needed.entry(month_of(d)).or_default() += 1;
+                d = d.succ_opt().unwrap();
+            }
+            let coverable = needed.iter().all(|(month, count)| {
+                used.get(month).copied().unwrap_or(0) + count <= freezes_per_month
+            });
+
+            if run > 0 && coverable {
+                for (month, count) in needed {
+                    *used.entry(month).or_default() += count;
+                }
+                let mut d = day;
+                while d < gap_end {
+                    frozen_days.push(d);
+                    d = d.succ_opt().unwrap();
+                }
+            } else {
+                run = 0;
+            }
+            day = gap_end;
+        }
+
+        if met(today) {
+            run += 1;
+            longest = longest.max(run);
+        }
+    }
+
+    let used_this_month = used.get(&month_of(today)).copied().unwrap_or(0);
+
+    StreakSummary {
+        current_streak: run,
+        longest_streak: longest,
+        frozen_days,
+        freezes_used_this_month: used_this_month,
+        freezes_remaining_this_month: freezes_per_month.saturating_sub(used_this_month),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    fn met(days: &[(u32, u32)]) -> BTreeSet<NaiveDate> {
+        days.iter().map(|&(month, day)| date(month, day)).collect()
+    }
+
+    #[test]
+    fn a_short_gap_is_frozen_without_counting_it() {
+        let days = met(&[(6, 1), (6, 2), (6, 4), (6, 5)]);
+        let summary = compute_streak(&days, date(6, 6), 2);
+
+        assert_eq!(summary.current_streak, 4);
+        assert_eq!(summary.longest_streak, 4);
+        assert_eq!(summary.frozen_days, vec![date(6, 3)]);
+        assert_eq!(summary.freezes_used_this_month, 1);
+        assert_eq!(summary.freezes_remaining_this_month, 1);
+    }
+
+    #[test]
+    fn a_gap_longer_than_the_freezes_breaks_the_streak() {
+        let days = met(&[(6, 1), (6, 2), (6, 3), (6, 7), (6, 8)]);
+        let summary = compute_streak(&days, date(6, 9), 2);
+
+        assert_eq!(summary.current_streak, 2);
+        assert_eq!(summary.longest_streak, 3);
+        assert!(summary.frozen_days.is_empty());
+        assert_eq!(summary.freezes_remaining_this_month, 2);
+    }
+
+    #[test]
+    fn a_gap_across_months_uses_each_month_s_freezes() {
+        let days = met(&[(5, 29), (5, 30), (6, 2)]);
+        let summary = compute_streak(&days, date(6, 3), 1);
+
+        assert_eq!(summary.current_streak, 3);
+        assert_eq!(summary.frozen_days, vec![date(5, 31), date(6, 1)]);
+        assert_eq!(summary.freezes_used_this_month, 1);
+        assert_eq!(summary.freezes_remaining_this_month, 0);
+
+        // 5月のフリーズを使い切っていれば、月をまたぐ空白は守れない
+        let days = met(&[(5, 27), (5, 29), (5, 30), (6, 2)]);
+        let summary = compute_streak(&days, date(6, 3), 1);
+        assert_eq!(summary.current_streak, 1);
+        assert_eq!(summary.longest_streak, 3);
+        assert_eq!(summary.frozen_days, vec![date(5, 28)]);
+    }
+
+    #[test]
+    fn an_unfinished_today_does_not_break_the_streak() {
+        let days = met(&[(6, 1), (6, 2)]);
+        let summary = compute_streak(&days, date(6, 3), 0);
+        assert_eq!(summary.current_streak, 2);
+
+        let days = met(&[(6, 1), (6, 2), (6, 3)]);
+        let summary = compute_streak(&days, date(6, 3), 0);
+        assert_eq!(summary.current_streak, 3);
+        assert_eq!(summary.longest_streak, 3);
+    }
+
+    #[test]
+    fn goals_of_zero_need_some_activity() {
+        let goals = DailyGoals {
+            new_words: 0,
+            reviews: 0,
+        };
+        assert!(!goals.is_met(&DayActivity::default()));
+        assert!(goals.is_met(&DayActivity {
+            new_words: 0,
+            reviews: 1,
+        }));
+    }
+}